@@ -257,6 +257,8 @@ async fn eth_integration(web3: Web3) {
         None,
         None.into(),
         None,
+        Default::default(),
+        None,
     );
     driver.single_run().await.unwrap();
 
@@ -262,6 +262,8 @@ async fn onchain_settlement(web3: Web3) {
         None,
         None.into(),
         None,
+        Default::default(),
+        None,
     );
     driver.single_run().await.unwrap();
 
@@ -165,11 +165,16 @@ impl OrderbookServices {
             Duration::from_secs(120),
             db.clone(),
             Default::default(),
+            Default::default(),
+            Default::default(),
             balance_fetcher.clone(),
             bad_token_detector.clone(),
             current_block_stream.clone(),
             native_price_estimator,
+            None,
+            contracts.weth.address(),
             Arc::new(NoopMetrics),
+            Default::default(),
         );
         let order_validator = Arc::new(OrderValidator::new(
             Box::new(web3.clone()),
@@ -251,6 +251,8 @@ async fn onchain_settlement_without_liquidity(web3: Web3) {
         None,
         None.into(),
         None,
+        Default::default(),
+        None,
     );
     driver.single_run().await.unwrap();
 
@@ -220,6 +220,8 @@ async fn smart_contract_orders(web3: Web3) {
         None,
         None.into(),
         None,
+        Default::default(),
+        None,
     );
     driver.single_run().await.unwrap();
 
@@ -201,6 +201,8 @@ async fn vault_balances(web3: Web3) {
         None,
         None.into(),
         None,
+        Default::default(),
+        None,
     );
     driver.single_run().await.unwrap();
 
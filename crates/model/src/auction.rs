@@ -6,6 +6,10 @@ use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use std::collections::BTreeMap;
 
+/// The fixed-point scale of the auction prices. A token with a price of `NATIVE_PRICE_SCALE` is
+/// worth exactly as much as the native token, so the native token itself has this price.
+pub const NATIVE_PRICE_SCALE: u128 = 1_000_000_000_000_000_000;
+
 /// A batch auction.
 #[serde_as]
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -25,7 +29,8 @@ pub struct Auction {
     /// The solvable orders included in the auction.
     pub orders: Vec<Order>,
 
-    /// The reference prices for all traded tokens in the auction.
+    /// The reference prices for all traded tokens in the auction, in native token scaled by
+    /// [`NATIVE_PRICE_SCALE`].
     #[serde_as(as = "BTreeMap<_, DecimalU256>")]
     pub prices: BTreeMap<H160, U256>,
 }
@@ -37,6 +42,11 @@ mod tests {
     use maplit::btreemap;
     use serde_json::json;
 
+    #[test]
+    fn native_price_scale_is_1e18() {
+        assert_eq!(U256::from(NATIVE_PRICE_SCALE), U256::exp10(18));
+    }
+
     #[test]
     fn roundtrips_auction() {
         let order = |uid_byte: u8| Order {
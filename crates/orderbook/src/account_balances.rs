@@ -4,11 +4,13 @@ use ethcontract::{batch::CallBatch, Account};
 use futures::{FutureExt, StreamExt};
 use model::order::{Order, SellTokenSource};
 use primitive_types::{H160, U256};
+use serde::Serialize;
 use shared::{Web3, Web3Transport};
 use std::future::Future;
 use web3::types::{BlockId, BlockNumber, CallRequest};
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Query {
     pub owner: H160,
     pub token: H160,
@@ -16,6 +18,9 @@ pub struct Query {
 }
 
 impl Query {
+    /// The balance of an order is always queried for its owner. For orders of smart contracts,
+    /// which are pre-signed, the owner is the contract itself and not the account that sent the
+    /// pre-signature transaction, so the balance of the contract gets checked.
     pub fn from_order(o: &Order) -> Self {
         Self {
             owner: o.metadata.owner,
@@ -21,10 +21,10 @@ use orderbook::{
     metrics::Metrics,
     orderbook::Orderbook,
     serve_api,
-    solvable_orders::SolvableOrdersCache,
+    solvable_orders::{OrderPrioritization, SolvableOrdersCache, SolvableOrdersCacheConfig},
     verify_deployed_contract_constants,
 };
-use primitive_types::{H160, U256};
+use primitive_types::{H160, H256, U256};
 use shared::{
     bad_token::{
         cache::CachingDetector,
@@ -47,7 +47,7 @@ use shared::{
         baseline::BaselinePriceEstimator,
         competition::{CompetitionPriceEstimator, RacingCompetitionPriceEstimator},
         instrumented::InstrumentedPriceEstimator,
-        native::NativePriceEstimator,
+        native::{NativePriceEstimating, NativePriceEstimator},
         native_price_cache::CachingNativePriceEstimator,
         oneinch::OneInchPriceEstimator,
         paraswap::ParaswapPriceEstimator,
@@ -120,6 +120,10 @@ struct Arguments {
     #[clap(long, env, use_value_delimiter = true)]
     banned_users: Vec<H160>,
 
+    /// List of app data hashes whose orders are not included in the auction.
+    #[clap(long, env, use_value_delimiter = true)]
+    banned_app_data: Vec<H256>,
+
     /// List of token addresses that should be allowed regardless of whether the bad token detector
     /// thinks they are bad. Base tokens are automatically allowed.
     #[clap(long, env, use_value_delimiter = true)]
@@ -217,15 +221,23 @@ struct Arguments {
     )]
     native_price_estimators: Vec<PriceEstimatorType>,
 
+    /// Which estimators to use to estimate native prices of tokens that the primary native price
+    /// estimators could not price when building the auction. No fallback is used if empty.
+    #[clap(long, env, arg_enum, use_value_delimiter = true)]
+    fallback_native_price_estimators: Vec<PriceEstimatorType>,
+
     /// The amount in native tokens atoms to use for price estimation. Should be reasonably large so
     /// that small pools do not influence the prices. If not set a reasonable default is used based
     /// on network id.
+    ///
+    /// Specific tokens can use different amounts for native price estimation by adding comma
+    /// separated `token=amount` pairs, for example `1000000000000000000,0x6b17...1d0f=500`.
     #[clap(
         long,
         env,
-        parse(try_from_str = U256::from_dec_str)
+        parse(try_from_str = parse_estimation_amounts)
     )]
-    amount_to_estimate_prices_with: Option<U256>,
+    amount_to_estimate_prices_with: Option<EstimationAmounts>,
 
     #[clap(
         long,
@@ -247,6 +259,79 @@ struct Arguments {
 
     #[clap(long, env, default_value = "static", arg_enum)]
     token_detector_fee_values: FeeValues,
+
+    /// The maximum number of orders of a single owner that get included in the auction. If not
+    /// set the number of orders per owner is unlimited.
+    #[clap(long, env)]
+    max_orders_per_user: Option<usize>,
+
+    /// The minimum value in wei of the native token that either the sell or the buy amount of an
+    /// order needs to be worth for the order to be included in the auction.
+    #[clap(long, env, parse(try_from_str = U256::from_dec_str))]
+    min_order_native_value: Option<U256>,
+
+    /// The maximum number of balance queries that get sent to the node in a single batch when
+    /// updating the solvable orders.
+    #[clap(long, env, default_value = "5000")]
+    balance_query_chunk_size: usize,
+
+    /// The fraction by which the interval between solvable orders updates randomly varies so that
+    /// multiple orderbook replicas don't update at the same time.
+    #[clap(long, env, default_value = "0.2")]
+    update_interval_jitter: f64,
+
+    /// Orders whose limit price is worse than the price implied by the native token prices by more
+    /// than this many basis points are not included in the auction. Disabled if not specified.
+    #[clap(long, env)]
+    max_limit_price_deviation_bps: Option<u32>,
+
+    /// How many of the most recently published auctions are kept in memory for debugging.
+    #[clap(long, env, default_value = "10")]
+    auction_history_size: usize,
+
+    /// The maximum time between solvable orders updates when updates keep failing. The time
+    /// between updates doubles with every consecutive failure up to this value.
+    #[clap(
+        long,
+        env,
+        default_value = "60",
+        parse(try_from_str = shared::arguments::duration_from_seconds),
+    )]
+    max_solvable_orders_update_backoff: Duration,
+
+    /// The maximum number of tokens whose native price is estimated at the same time when
+    /// building the auction. Unlimited if not specified.
+    #[clap(long, env)]
+    max_concurrent_native_price_estimates: Option<usize>,
+
+    /// The fraction of the time allotted for native price estimation while building the auction
+    /// after which a warning is logged if the estimates are still not done.
+    #[clap(long, env, default_value = "0.8")]
+    native_price_soft_deadline_fraction: f64,
+
+    /// Orders are only included in the auction once they are at least this many seconds old.
+    #[clap(
+        long,
+        env,
+        parse(try_from_str = shared::arguments::duration_from_seconds),
+    )]
+    min_order_age: Option<Duration>,
+
+    /// The maximum size in bytes of the JSON serialized auction. The orders with the lowest value
+    /// are removed from larger auctions. Unlimited if not specified.
+    #[clap(long, env)]
+    max_auction_size: Option<usize>,
+
+    /// How to choose which orders to include in the auction when an owner does not have enough
+    /// balance for all of their orders selling the same token.
+    #[clap(long, env, arg_enum, default_value = "creation-date")]
+    order_prioritization: OrderPrioritization,
+
+    /// Comma separated `token=price` pairs of native prices (in wei of native token per 10^18
+    /// base units of the token) that are used instead of estimating them. Meant for test
+    /// environments without liquidity.
+    #[clap(long, env, parse(try_from_str = parse_native_prices))]
+    static_native_prices: Option<HashMap<H160, U256>>,
 }
 
 pub async fn database_metrics(metrics: Arc<Metrics>, database: Postgres) -> ! {
@@ -270,12 +355,14 @@ async fn main() {
         args.shared.log_filter.as_str(),
         args.shared.log_stderr_threshold,
     );
+    args.shared.validate();
     tracing::info!("running order book with validated {:#?}", args);
 
     setup_metrics_registry(Some("gp_v2_api".into()), None);
     let metrics = Arc::new(Metrics::new().unwrap());
 
-    let client = shared::http_client(args.shared.http_timeout);
+    let client = shared::http_client(&args.shared);
+    let http_retry_policy = shared::http_retry_policy(&args.shared);
 
     let transport = create_instrumented_transport(
         HttpTransport::new(client.clone(), args.shared.node_url.clone(), "".to_string()),
@@ -314,8 +401,15 @@ async fn main() {
 
     let native_token_price_estimation_amount = args
         .amount_to_estimate_prices_with
+        .as_ref()
+        .and_then(|amounts| amounts.default)
         .or_else(|| default_amount_to_estimate_prices_with(&network))
         .expect("No amount to estimate prices with set.");
+    let native_token_price_estimation_amount_overrides = args
+        .amount_to_estimate_prices_with
+        .as_ref()
+        .map(|amounts| amounts.overrides.clone())
+        .unwrap_or_default();
 
     let vault = match BalancerV2Vault::deployed(&web3).await {
         Ok(contract) => Some(contract),
@@ -370,9 +464,10 @@ async fn main() {
         metrics.clone(),
     ));
 
-    let baseline_sources = args.shared.baseline_sources.unwrap_or_else(|| {
-        sources::defaults_for_chain(chain_id).expect("failed to get default baseline sources")
-    });
+    let baseline_sources = args
+        .shared
+        .baseline_sources_for_chain(chain_id)
+        .expect("failed to get baseline sources");
     tracing::info!(?baseline_sources, "using baseline sources");
     let (pair_providers, pool_fetchers): (Vec<_>, Vec<_>) =
         sources::uniswap_like_liquidity_sources(&web3, &baseline_sources)
@@ -499,11 +594,12 @@ async fn main() {
             args.shared.zeroex_api_key.clone(),
             client.clone(),
         )
-        .unwrap(),
+        .unwrap()
+        .with_retry_policy(http_retry_policy),
     );
     let one_inch_api =
         OneInchClientImpl::new(args.shared.one_inch_url.clone(), client.clone(), chain_id)
-            .map(Arc::new);
+            .map(|api| Arc::new(api.with_retry_policy(http_retry_policy)));
     let instrumented = |inner: Box<dyn PriceEstimating>, name: String| {
         InstrumentedPriceEstimator::new(inner, name, metrics.clone())
     };
@@ -520,6 +616,7 @@ async fn main() {
                 Arc::new(DefaultParaswapApi {
                     client: client.clone(),
                     partner: args.shared.paraswap_partner.clone().unwrap_or_default(),
+                    retry_policy: http_retry_policy,
                 }),
                 token_info_fetcher.clone(),
                 args.shared.disabled_paraswap_dexs.clone(),
@@ -534,6 +631,7 @@ async fn main() {
                         "quasimodo solver url is required when using quasimodo price estimation",
                     ),
                     client: client.clone(),
+                    retry_policy: http_retry_policy,
                     config: SolverConfig {
                         api_key: None,
                         max_nr_exec_orders: 100,
@@ -592,16 +690,19 @@ async fn main() {
     ))));
 
     let native_price_estimator = Arc::new(CachingNativePriceEstimator::new(
-        Box::new(NativePriceEstimator::new(
-            Arc::new(sanitized(Box::new(CompetitionPriceEstimator::new(
-                args.native_price_estimators
-                    .iter()
-                    .map(|estimator| create_base_estimator(*estimator))
-                    .collect(),
-            )))),
-            native_token.address(),
-            native_token_price_estimation_amount,
-        )),
+        Box::new(
+            NativePriceEstimator::new(
+                Arc::new(sanitized(Box::new(CompetitionPriceEstimator::new(
+                    args.native_price_estimators
+                        .iter()
+                        .map(|estimator| create_base_estimator(*estimator))
+                        .collect(),
+                )))),
+                native_token.address(),
+                native_token_price_estimation_amount,
+            )
+            .with_amount_overrides(native_token_price_estimation_amount_overrides.clone()),
+        ),
         args.native_price_cache_max_age_secs,
         metrics.clone(),
     ));
@@ -609,6 +710,22 @@ async fn main() {
         Duration::from_secs(1),
         Some(args.native_price_cache_max_update_size),
     );
+    let fallback_native_price_estimator =
+        (!args.fallback_native_price_estimators.is_empty()).then(|| {
+            Arc::new(
+                NativePriceEstimator::new(
+                    Arc::new(sanitized(Box::new(CompetitionPriceEstimator::new(
+                        args.fallback_native_price_estimators
+                            .iter()
+                            .map(|estimator| create_base_estimator(*estimator))
+                            .collect(),
+                    )))),
+                    native_token.address(),
+                    native_token_price_estimation_amount,
+                )
+                .with_amount_overrides(native_token_price_estimation_amount_overrides),
+            ) as Arc<dyn NativePriceEstimating>
+        });
 
     let cow_token = match CowProtocolToken::deployed(&web3).await {
         Err(DeployError::NotFound(_)) => None,
@@ -662,12 +779,36 @@ async fn main() {
         args.min_order_validity_period,
         database.clone(),
         args.banned_users.iter().copied().collect(),
+        args.allowed_tokens.iter().copied().collect(),
+        args.banned_app_data.iter().copied().collect(),
         balance_fetcher.clone(),
         bad_token_detector.clone(),
         current_block_stream.clone(),
         native_price_estimator,
+        fallback_native_price_estimator,
+        native_token.address(),
         metrics.clone(),
+        SolvableOrdersCacheConfig {
+            max_orders_per_user: args.max_orders_per_user,
+            order_prioritization: args.order_prioritization,
+            min_order_native_value: args.min_order_native_value,
+            balance_query_chunk_size: args.balance_query_chunk_size,
+            update_interval_jitter: args.update_interval_jitter,
+            max_limit_price_deviation_bps: args.max_limit_price_deviation_bps,
+            auction_history_size: args.auction_history_size,
+            max_update_backoff: args.max_solvable_orders_update_backoff,
+            max_concurrent_native_price_estimates: args.max_concurrent_native_price_estimates,
+            liquidity_order_owners: args.shared.liquidity_order_owners.iter().copied().collect(),
+            native_price_soft_deadline_fraction: args.native_price_soft_deadline_fraction,
+            min_order_age: args.min_order_age,
+            max_auction_size: args.max_auction_size,
+            static_native_prices: args.static_native_prices.unwrap_or_default(),
+            ..Default::default()
+        },
     );
+    if let Err(err) = solvable_orders_cache.prewarm().await {
+        tracing::warn!(?err, "failed to prewarm solvable orders balances");
+    }
     let block = current_block_stream.borrow().number.unwrap().as_u64();
     solvable_orders_cache
         .update(block)
@@ -810,6 +951,53 @@ fn parse_partner_fee_factor(s: &str) -> Result<HashMap<AppId, f64>> {
     Ok(res)
 }
 
+/// Amounts to use for price estimation with optional per token overrides.
+#[derive(Debug)]
+struct EstimationAmounts {
+    default: Option<U256>,
+    overrides: HashMap<H160, U256>,
+}
+
+/// Parses a comma separated list of amounts where at most one amount is the default and all others
+/// are per token overrides in the form `token=amount`.
+fn parse_estimation_amounts(s: &str) -> Result<EstimationAmounts> {
+    let mut amounts = EstimationAmounts {
+        default: None,
+        overrides: HashMap::new(),
+    };
+    for entry in s
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        match entry.split_once('=') {
+            Some((token, amount)) => {
+                let token = token.trim().parse().context("failed to parse token")?;
+                let amount = U256::from_dec_str(amount.trim()).context("failed to parse amount")?;
+                if amounts.overrides.insert(token, amount).is_some() {
+                    return Err(anyhow!("duplicate amount for token {:?}", token));
+                }
+            }
+            None => {
+                let amount = U256::from_dec_str(entry).context("failed to parse amount")?;
+                if amounts.default.replace(amount).is_some() {
+                    return Err(anyhow!("multiple default amounts"));
+                }
+            }
+        }
+    }
+    Ok(amounts)
+}
+
+/// Parses a comma separated list of native prices in the form `token=price`.
+fn parse_native_prices(s: &str) -> Result<HashMap<H160, U256>> {
+    let prices = parse_estimation_amounts(s)?;
+    if prices.default.is_some() {
+        return Err(anyhow!("native prices must be specified as token=price"));
+    }
+    Ok(prices.overrides)
+}
+
 fn default_amount_to_estimate_prices_with(network_id: &str) -> Option<U256> {
     match network_id {
         // Mainnet, Rinkeby
@@ -825,6 +1013,33 @@ mod tests {
     use super::*;
     use maplit::hashmap;
 
+    #[test]
+    fn parses_estimation_amounts() {
+        let token = H160([0x11; 20]);
+        let amounts = parse_estimation_amounts(&format!("1000, {:?}=42", token)).unwrap();
+        assert_eq!(amounts.default, Some(1000.into()));
+        assert_eq!(amounts.overrides, hashmap! { token => 42.into() });
+
+        let amounts = parse_estimation_amounts(&format!("{:?}=42", token)).unwrap();
+        assert_eq!(amounts.default, None);
+        assert_eq!(amounts.overrides, hashmap! { token => 42.into() });
+
+        assert!(parse_estimation_amounts("1000,2000").is_err());
+        assert!(parse_estimation_amounts(&format!("{:?}=1,{:?}=2", token, token)).is_err());
+        assert!(parse_estimation_amounts("0x11=1").is_err());
+        assert!(parse_estimation_amounts("not an amount").is_err());
+    }
+
+    #[test]
+    fn parses_native_prices() {
+        let token = H160([0x11; 20]);
+        assert_eq!(
+            parse_native_prices(&format!("{:?}=42", token)).unwrap(),
+            hashmap! { token => 42.into() }
+        );
+        assert!(parse_native_prices("1000").is_err());
+    }
+
     #[test]
     fn parse_partner_fee_factor_ok() {
         let x = "0x0000000000000000000000000000000000000000000000000000000000000000";
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use gas_estimation::EstimatedGasPrice;
+use model::order::OrderKind;
 use prometheus::{
     Gauge, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
     IntGaugeVec, Opts,
@@ -12,7 +13,7 @@ use shared::{
     },
     transport::instrumented::TransportMetrics,
 };
-use std::time::Duration;
+use std::{collections::VecDeque, sync::Mutex, time::Duration};
 
 pub struct Metrics {
     db_table_row_count: IntGaugeVec,
@@ -32,8 +33,27 @@ pub struct Metrics {
     auction_filtered_orders: IntGauge,
     auction_errored_price_estimates: IntCounter,
     auction_price_estimate_timeouts: IntCounter,
+    auction_stale_updates: IntCounter,
+    auction_duplicate_orders: IntCounter,
+    auction_native_price_overlap: IntCounterVec,
+    auction_self_trade_orders: IntCounter,
+    auction_skipped_price_estimates: IntCounter,
+    auction_removed_orders: IntCounterVec,
+    auction_order_changes: IntCounterVec,
+    auction_update_duration: Histogram,
+    auction_update_failures: IntCounter,
+    auction_cached_balances: IntGauge,
+    auction_max_transfer_out_amount_failures: IntCounterVec,
+    auction_order_types: IntGaugeVec,
+    auction_native_price_soft_timeouts: IntCounter,
+    auction_fallback_native_prices: IntCounter,
+    auction_update_panics: IntCounter,
+    auction_update_history: AuctionUpdateHistory,
 }
 
+/// The number of `auction_updated` samples that are kept in memory.
+const AUCTION_UPDATE_HISTORY_SIZE: usize = 100;
+
 impl Metrics {
     pub fn new() -> Result<Self> {
         let registry = get_metrics_registry();
@@ -122,6 +142,111 @@ impl Metrics {
         )?;
         registry.register(Box::new(auction_price_estimate_timeouts.clone()))?;
 
+        let auction_stale_updates = IntCounter::new(
+            "auction_stale_updates",
+            "Number of times the previous auction was kept because no order could be priced.",
+        )?;
+        registry.register(Box::new(auction_stale_updates.clone()))?;
+
+        let auction_duplicate_orders = IntCounter::new(
+            "auction_duplicate_orders",
+            "Number of orders with a duplicate uid that were removed from the auction.",
+        )?;
+        registry.register(Box::new(auction_duplicate_orders.clone()))?;
+
+        let auction_native_price_overlap = IntCounterVec::new(
+            Opts::new(
+                "auction_native_price_overlap",
+                "Number of auction tokens whose native price was or was not part of the previous auction.",
+            ),
+            &["previous_auction"],
+        )?;
+        registry.register(Box::new(auction_native_price_overlap.clone()))?;
+
+        let auction_self_trade_orders = IntCounter::new(
+            "auction_self_trade_orders",
+            "Number of orders selling and buying the same token that were removed from the auction.",
+        )?;
+        registry.register(Box::new(auction_self_trade_orders.clone()))?;
+
+        let auction_skipped_price_estimates = IntCounter::new(
+            "auction_skipped_price_estimates",
+            "Number of native price estimates skipped because the token recently could not be priced.",
+        )?;
+        registry.register(Box::new(auction_skipped_price_estimates.clone()))?;
+
+        let auction_removed_orders = IntCounterVec::new(
+            Opts::new(
+                "auction_removed_orders",
+                "Number of orders removed from the auction by reason.",
+            ),
+            &["reason"],
+        )?;
+        registry.register(Box::new(auction_removed_orders.clone()))?;
+
+        let auction_order_changes = IntCounterVec::new(
+            Opts::new(
+                "auction_order_changes",
+                "Number of orders that entered or left the auction between updates.",
+            ),
+            &["change"],
+        )?;
+        registry.register(Box::new(auction_order_changes.clone()))?;
+
+        let auction_update_duration = Histogram::with_opts(HistogramOpts::new(
+            "auction_update_duration_seconds",
+            "Time it takes to successfully update the solvable orders and build the auction.",
+        ))?;
+        registry.register(Box::new(auction_update_duration.clone()))?;
+
+        let auction_update_failures = IntCounter::new(
+            "auction_update_failures",
+            "Number of failed updates of the solvable orders.",
+        )?;
+        registry.register(Box::new(auction_update_failures.clone()))?;
+
+        let auction_cached_balances = IntGauge::new(
+            "auction_cached_balances",
+            "Number of balances cached by the solvable orders cache.",
+        )?;
+        registry.register(Box::new(auction_cached_balances.clone()))?;
+
+        let auction_max_transfer_out_amount_failures = IntCounterVec::new(
+            Opts::new(
+                "auction_max_transfer_out_amount_failures",
+                "Number of orders skipped because their transfer out amount could not be computed.",
+            ),
+            &["kind"],
+        )?;
+        registry.register(Box::new(auction_max_transfer_out_amount_failures.clone()))?;
+
+        let auction_order_types = IntGaugeVec::new(
+            Opts::new(
+                "auction_order_types",
+                "Number of user and liquidity orders in the currently published auction.",
+            ),
+            &["type"],
+        )?;
+        registry.register(Box::new(auction_order_types.clone()))?;
+
+        let auction_native_price_soft_timeouts = IntCounter::new(
+            "auction_native_price_soft_timeouts",
+            "Number of auction updates for which native price estimation hit the soft deadline.",
+        )?;
+        registry.register(Box::new(auction_native_price_soft_timeouts.clone()))?;
+
+        let auction_fallback_native_prices = IntCounter::new(
+            "auction_fallback_native_prices",
+            "Number of native prices that came from the secondary native price estimator.",
+        )?;
+        registry.register(Box::new(auction_fallback_native_prices.clone()))?;
+
+        let auction_update_panics = IntCounter::new(
+            "auction_update_panics",
+            "Number of updates of the solvable orders that panicked.",
+        )?;
+        registry.register(Box::new(auction_update_panics.clone()))?;
+
         Ok(Self {
             db_table_row_count,
             rpc_requests,
@@ -137,9 +262,30 @@ impl Metrics {
             auction_filtered_orders,
             auction_errored_price_estimates,
             auction_price_estimate_timeouts,
+            auction_stale_updates,
+            auction_duplicate_orders,
+            auction_native_price_overlap,
+            auction_self_trade_orders,
+            auction_skipped_price_estimates,
+            auction_removed_orders,
+            auction_order_changes,
+            auction_update_duration,
+            auction_update_failures,
+            auction_cached_balances,
+            auction_max_transfer_out_amount_failures,
+            auction_order_types,
+            auction_native_price_soft_timeouts,
+            auction_fallback_native_prices,
+            auction_update_panics,
+            auction_update_history: AuctionUpdateHistory::new(AUCTION_UPDATE_HISTORY_SIZE),
         })
     }
 
+    /// Returns the most recent `auction_updated` samples from oldest to newest.
+    pub fn auction_update_samples(&self) -> Vec<AuctionUpdateSample> {
+        self.auction_update_history.samples()
+    }
+
     pub fn set_table_row_count(&self, table: &str, count: i64) {
         self.db_table_row_count
             .with_label_values(&[table])
@@ -178,6 +324,92 @@ impl crate::solvable_orders::AuctionMetrics for Metrics {
         self.auction_filtered_orders.set(filtered_orders as i64);
         self.auction_errored_price_estimates
             .inc_by(errored_estimates);
+        self.auction_update_history.record(AuctionUpdateSample {
+            time: chrono::Utc::now(),
+            solvable_orders,
+            filtered_orders,
+            errored_estimates,
+            timeout,
+        });
+    }
+
+    fn auction_update_stale(&self) {
+        self.auction_stale_updates.inc();
+    }
+
+    fn duplicate_orders_removed(&self, count: u64) {
+        self.auction_duplicate_orders.inc_by(count);
+    }
+
+    fn native_price_overlap(&self, known: u64, new: u64) {
+        self.auction_native_price_overlap
+            .with_label_values(&["known"])
+            .inc_by(known);
+        self.auction_native_price_overlap
+            .with_label_values(&["new"])
+            .inc_by(new);
+    }
+
+    fn self_trade_orders_removed(&self, count: u64) {
+        self.auction_self_trade_orders.inc_by(count);
+    }
+
+    fn native_price_estimates_skipped(&self, count: u64) {
+        self.auction_skipped_price_estimates.inc_by(count);
+    }
+
+    fn orders_removed(&self, reason: &'static str, count: u64) {
+        self.auction_removed_orders
+            .with_label_values(&[reason])
+            .inc_by(count);
+    }
+
+    fn auction_orders_changed(&self, added: u64, removed: u64) {
+        self.auction_order_changes
+            .with_label_values(&["added"])
+            .inc_by(added);
+        self.auction_order_changes
+            .with_label_values(&["removed"])
+            .inc_by(removed);
+    }
+
+    fn auction_update_duration(&self, duration: Duration) {
+        self.auction_update_duration.observe(duration.as_secs_f64());
+    }
+
+    fn auction_update_failed(&self) {
+        self.auction_update_failures.inc();
+    }
+
+    fn balances_cached(&self, count: u64) {
+        self.auction_cached_balances.set(count as i64);
+    }
+
+    fn auction_order_types(&self, user_orders: u64, liquidity_orders: u64) {
+        self.auction_order_types
+            .with_label_values(&["UserOrder"])
+            .set(user_orders as i64);
+        self.auction_order_types
+            .with_label_values(&["LiquidityOrder"])
+            .set(liquidity_orders as i64);
+    }
+
+    fn native_price_estimation_slow(&self) {
+        self.auction_native_price_soft_timeouts.inc();
+    }
+
+    fn native_prices_from_fallback(&self, count: u64) {
+        self.auction_fallback_native_prices.inc_by(count);
+    }
+
+    fn auction_update_panicked(&self) {
+        self.auction_update_panics.inc();
+    }
+
+    fn max_transfer_out_amount_failed(&self, kind: OrderKind) {
+        self.auction_max_transfer_out_amount_failures
+            .with_label_values(&[kind.label()])
+            .inc();
     }
 }
 
@@ -240,4 +472,86 @@ pub struct NoopMetrics;
 
 impl crate::solvable_orders::AuctionMetrics for NoopMetrics {
     fn auction_updated(&self, _: u64, _: u64, _: u64, _: bool) {}
+    fn auction_update_stale(&self) {}
+    fn duplicate_orders_removed(&self, _: u64) {}
+    fn native_price_overlap(&self, _: u64, _: u64) {}
+    fn self_trade_orders_removed(&self, _: u64) {}
+    fn native_price_estimates_skipped(&self, _: u64) {}
+    fn orders_removed(&self, _: &'static str, _: u64) {}
+    fn auction_orders_changed(&self, _: u64, _: u64) {}
+    fn auction_update_duration(&self, _: Duration) {}
+    fn auction_update_failed(&self) {}
+    fn balances_cached(&self, _: u64) {}
+    fn max_transfer_out_amount_failed(&self, _: OrderKind) {}
+    fn auction_order_types(&self, _: u64, _: u64) {}
+    fn native_price_estimation_slow(&self) {}
+    fn native_prices_from_fallback(&self, _: u64) {}
+    fn auction_update_panicked(&self) {}
+}
+
+/// A single `auction_updated` call recorded by `AuctionUpdateHistory`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AuctionUpdateSample {
+    pub time: chrono::DateTime<chrono::Utc>,
+    pub solvable_orders: u64,
+    pub filtered_orders: u64,
+    pub errored_estimates: u64,
+    pub timeout: bool,
+}
+
+/// Keeps the most recent `auction_updated` samples in memory so that they can be inspected
+/// without a Prometheus scrape.
+pub struct AuctionUpdateHistory {
+    capacity: usize,
+    samples: Mutex<VecDeque<AuctionUpdateSample>>,
+}
+
+impl AuctionUpdateHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            samples: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Returns the recorded samples from oldest to newest.
+    pub fn samples(&self) -> Vec<AuctionUpdateSample> {
+        self.samples.lock().unwrap().iter().copied().collect()
+    }
+
+    fn record(&self, sample: AuctionUpdateSample) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auction_update_history_keeps_most_recent_samples() {
+        let history = AuctionUpdateHistory::new(2);
+        for solvable_orders in 0..3 {
+            history.record(AuctionUpdateSample {
+                time: chrono::Utc::now(),
+                solvable_orders,
+                filtered_orders: 0,
+                errored_estimates: 0,
+                timeout: false,
+            });
+        }
+        let samples = history
+            .samples()
+            .iter()
+            .map(|sample| sample.solvable_orders)
+            .collect::<Vec<_>>();
+        assert_eq!(samples, [1, 2]);
+    }
 }
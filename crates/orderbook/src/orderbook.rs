@@ -173,6 +173,7 @@ impl Orderbook {
         self.database
             .cancel_order(&order.metadata.uid, Utc::now())
             .await?;
+        self.solvable_orders.evict_order(&order.metadata.uid);
         Ok(())
     }
 
@@ -193,7 +194,12 @@ impl Orderbook {
         }
         set_available_balances(orders.as_mut_slice(), &self.solvable_orders);
         if filter.exclude_unsupported_tokens {
-            orders = filter_unsupported_tokens(orders, self.bad_token_detector.as_ref()).await?;
+            orders = filter_unsupported_tokens(
+                orders,
+                self.bad_token_detector.as_ref(),
+                &self.solvable_orders.trusted_tokens(),
+            )
+            .await?;
         }
         Ok(orders)
     }
@@ -250,19 +256,26 @@ impl Orderbook {
 #[async_trait::async_trait]
 impl LivenessChecking for Orderbook {
     async fn is_alive(&self) -> bool {
-        self.get_solvable_orders().is_ok()
+        self.solvable_orders
+            .is_fresh(self.solvable_orders_max_update_age)
     }
 }
 
+/// Removes orders trading tokens that the bad token detector considers unsupported. Trusted tokens
+/// are treated as supported without consulting the detector.
 pub async fn filter_unsupported_tokens(
     mut orders: Vec<Order>,
     bad_token: &dyn BadTokenDetecting,
+    trusted_tokens: &HashSet<H160>,
 ) -> Result<Vec<Order>> {
     // Can't use normal `retain` or `filter` because the bad token detection is async. So either
     // this manual iteration or conversion to stream.
     let mut index = 0;
     'outer: while index < orders.len() {
         for token in orders[index].creation.token_pair().unwrap() {
+            if trusted_tokens.contains(&token) {
+                continue;
+            }
             if !bad_token.detect(token).await?.is_good() {
                 orders.swap_remove(index);
                 continue 'outer;
@@ -308,10 +321,17 @@ mod tests {
                 .with_buy_token(token2)
                 .build(),
         ];
-        let result = filter_unsupported_tokens(orders.clone(), &bad_token)
+        let result = filter_unsupported_tokens(orders.clone(), &bad_token, &Default::default())
             .now_or_never()
             .unwrap()
             .unwrap();
         assert_eq!(result, &orders[1..2]);
+
+        let result =
+            filter_unsupported_tokens(orders.clone(), &bad_token, &maplit::hashset! {token0})
+                .now_or_never()
+                .unwrap()
+                .unwrap();
+        assert_eq!(result, orders);
     }
 }
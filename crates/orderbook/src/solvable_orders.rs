@@ -1,29 +1,50 @@
 use crate::{
     account_balances::{BalanceFetching, Query},
     database::orders::OrderStoring,
+    metrics::NoopMetrics,
     orderbook::filter_unsupported_tokens,
 };
 use anyhow::{Context as _, Result};
-use futures::StreamExt;
-use model::{auction::Auction, order::Order};
-use primitive_types::{H160, U256};
+use futures::{FutureExt as _, StreamExt};
+use model::{
+    auction::{Auction, NATIVE_PRICE_SCALE},
+    order::{Order, OrderKind, OrderUid, SellTokenSource},
+};
+use primitive_types::{H160, H256, U256, U512};
+use rand::Rng as _;
+use serde::Serialize;
 use shared::{
-    bad_token::BadTokenDetecting, current_block::CurrentBlockStream, maintenance::Maintaining,
-    price_estimation::native::NativePriceEstimating, time::now_in_epoch_seconds,
+    bad_token::BadTokenDetecting,
+    current_block::CurrentBlockStream,
+    maintenance::Maintaining,
+    price_estimation::{native::NativePriceEstimating, PriceEstimationError},
+    time::now_in_epoch_seconds,
 };
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    any::Any,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     iter::FromIterator,
-    sync::{Arc, Mutex, Weak},
-    time::Duration,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak,
+    },
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    sync::{watch, Notify},
+    task::JoinHandle,
+    time::Instant,
 };
-use tokio::{sync::Notify, time::Instant};
 
 // When creating the auction after solvable orders change we need to fetch native prices for a
 // potentially large amount of tokens. This is the maximum amount of time we allot for this
 // operation.
 const MAX_AUCTION_CREATION_TIME: Duration = Duration::from_secs(10);
 
+// The maximum number of chunks of balance queries that are in flight at the same time.
+const MAX_CONCURRENT_BALANCE_REQUESTS: usize = 4;
+
 pub trait AuctionMetrics: Send + Sync + 'static {
     fn auction_updated(
         &self,
@@ -32,6 +53,60 @@ pub trait AuctionMetrics: Send + Sync + 'static {
         errored_estimates: u64,
         timeout: bool,
     );
+
+    /// Called when an update produced no priced orders and the previous auction was kept.
+    fn auction_update_stale(&self);
+
+    /// Called with the number of orders that were dropped because their uid was duplicated.
+    fn duplicate_orders_removed(&self, count: u64);
+
+    /// Called with the number of traded tokens whose native price was already part of the
+    /// previously published auction and the number of traded tokens that were not. This measures
+    /// the overlap of the priced tokens between auctions, every token still gets estimated.
+    fn native_price_overlap(&self, known: u64, new: u64);
+
+    /// Called with the number of orders that were dropped because they sell and buy the same
+    /// token.
+    fn self_trade_orders_removed(&self, count: u64);
+
+    /// Called with the number of tokens that were not estimated because they recently could not
+    /// be priced.
+    fn native_price_estimates_skipped(&self, count: u64);
+
+    /// Called with the number of orders that were removed from the auction for the specified
+    /// reason. This is a breakdown of all orders that don't end up in the auction.
+    fn orders_removed(&self, reason: &'static str, count: u64);
+
+    /// Called with the number of orders that entered and left the auction compared to the
+    /// previously published one.
+    fn auction_orders_changed(&self, added: u64, removed: u64);
+
+    /// Called with the time it took to successfully update the solvable orders.
+    fn auction_update_duration(&self, duration: Duration);
+
+    /// Called when updating the solvable orders failed.
+    fn auction_update_failed(&self);
+
+    /// Called when the amount an order needs to transfer out could not be computed, for example
+    /// because its sell and fee amount overflow.
+    fn max_transfer_out_amount_failed(&self, kind: OrderKind);
+
+    /// Called with the number of balances that are cached after an update.
+    fn balances_cached(&self, count: u64);
+
+    /// Called with the number of user and liquidity orders in a newly published auction.
+    fn auction_order_types(&self, user_orders: u64, liquidity_orders: u64);
+
+    /// Called when native price estimation was still running at the soft deadline.
+    fn native_price_estimation_slow(&self);
+
+    /// Reports the number of native prices that came from the secondary estimator because the
+    /// primary one could not provide them.
+    fn native_prices_from_fallback(&self, count: u64);
+
+    /// Called when an update of the solvable orders panicked. The background update task keeps
+    /// running and tries again after the usual backoff.
+    fn auction_update_panicked(&self);
 }
 
 /// Keeps track and updates the set of currently solvable orders.
@@ -42,14 +117,137 @@ pub trait AuctionMetrics: Send + Sync + 'static {
 /// book.
 pub struct SolvableOrdersCache {
     min_order_validity_period: Duration,
+    config: SolvableOrdersCacheConfig,
     database: Arc<dyn OrderStoring>,
-    banned_users: HashSet<H160>,
+    banned_users: RwLock<HashSet<H160>>,
+    trusted_tokens: RwLock<HashSet<H160>>,
+    banned_app_data: RwLock<HashSet<H256>>,
     balance_fetcher: Arc<dyn BalanceFetching>,
     bad_token_detector: Arc<dyn BadTokenDetecting>,
+    current_block: CurrentBlockStream,
     notify: Notify,
     cache: Mutex<Inner>,
     native_price_estimator: Arc<dyn NativePriceEstimating>,
+    /// Queried for the tokens the primary native price estimator could not price.
+    secondary_native_price_estimator: Option<Arc<dyn NativePriceEstimating>>,
+    native_token: H160,
     auction_metrics: Arc<dyn AuctionMetrics>,
+    auction_sender: watch::Sender<Auction>,
+    /// The number of updates that were started and the number of the most recent update that
+    /// published new solvable orders.
+    updates_started: AtomicU64,
+    updates_published: watch::Sender<u64>,
+    shutdown: AtomicBool,
+    /// When the cache was created and how many milliseconds after that the background update
+    /// task last woke up.
+    created: Instant,
+    last_tick: AtomicU64,
+    /// Tokens that could not be priced and when that happened.
+    unpriceable_tokens: Mutex<HashMap<H160, Instant>>,
+    /// The most recently published auctions, oldest first.
+    auction_history: Mutex<VecDeque<Auction>>,
+    update_task_handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Optional tuning parameters for the solvable orders cache.
+#[derive(Clone, Debug)]
+pub struct SolvableOrdersCacheConfig {
+    /// The maximum number of orders of a single owner that get included in the auction. If an
+    /// owner has more solvable orders the most recent ones are kept.
+    pub max_orders_per_user: Option<usize>,
+
+    /// How to choose which orders to include when an owner does not have enough balance for all
+    /// of their orders selling the same token.
+    pub order_prioritization: OrderPrioritization,
+
+    /// Orders whose sell and buy amounts are both worth less than this amount of native token
+    /// (in wei) are not included in the auction.
+    pub min_order_native_value: Option<U256>,
+
+    /// The maximum number of balance queries that get sent to the balance fetcher at once.
+    pub balance_query_chunk_size: usize,
+
+    /// The fraction of the update interval by which the background update task randomly shortens
+    /// or extends each sleep so that multiple replicas do not update at the same time.
+    pub update_interval_jitter: f64,
+
+    /// How long a token for which the native price estimator reported no liquidity or an
+    /// unsupported token is not estimated again.
+    pub unpriceable_token_ttl: Duration,
+
+    /// Orders whose limit price is worse than the price implied by the native prices of their
+    /// tokens by more than this many basis points are not included in the auction.
+    pub max_limit_price_deviation_bps: Option<u32>,
+
+    /// How many of the most recently published auctions are kept for `auction_at_block`.
+    pub auction_history_size: usize,
+
+    /// The longest the background update task waits between updates after consecutive failures.
+    /// The wait doubles with every failed update starting from the regular update interval.
+    pub max_update_backoff: Duration,
+
+    /// The maximum number of tokens whose native price is estimated at the same time. All tokens
+    /// are estimated at once if not specified.
+    pub max_concurrent_native_price_estimates: Option<usize>,
+
+    /// Owners whose orders are liquidity orders. Only used to break down the auction orders in
+    /// the metrics.
+    pub liquidity_order_owners: HashSet<H160>,
+
+    /// The fraction of the time allotted for native price estimation after which a warning is
+    /// logged if the estimates are still not done.
+    pub native_price_soft_deadline_fraction: f64,
+
+    /// Orders are only included in the auction once they are at least this old.
+    pub min_order_age: Option<Duration>,
+
+    /// The maximum size in bytes of the JSON serialized auction. The orders with the lowest
+    /// native sell value are removed from larger auctions until they fit.
+    pub max_auction_size: Option<usize>,
+
+    /// Native prices (scaled by `NATIVE_PRICE_SCALE`) that are used for these tokens instead of
+    /// estimating them, for example in test environments without liquidity. Price overrides still
+    /// take precedence.
+    pub static_native_prices: HashMap<H160, U256>,
+}
+
+impl Default for SolvableOrdersCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_orders_per_user: None,
+            order_prioritization: Default::default(),
+            min_order_native_value: None,
+            balance_query_chunk_size: 5000,
+            update_interval_jitter: 0.2,
+            unpriceable_token_ttl: Duration::from_secs(60),
+            max_limit_price_deviation_bps: None,
+            auction_history_size: 10,
+            max_update_backoff: Duration::from_secs(60),
+            max_concurrent_native_price_estimates: None,
+            liquidity_order_owners: Default::default(),
+            native_price_soft_deadline_fraction: 0.8,
+            min_order_age: None,
+            max_auction_size: None,
+            static_native_prices: Default::default(),
+        }
+    }
+}
+
+/// The order in which orders of an owner selling the same token get allocated the owner's
+/// balance.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ArgEnum)]
+pub enum OrderPrioritization {
+    /// Most recently created orders first.
+    CreationDate,
+    /// Orders with the highest limit price (buy amount over sell amount) first. Orders with the
+    /// same limit price are ordered by creation date.
+    LimitPrice,
+}
+
+impl Default for OrderPrioritization {
+    fn default() -> Self {
+        Self::CreationDate
+    }
 }
 
 type Balances = HashMap<Query, U256>;
@@ -57,7 +255,15 @@ type Balances = HashMap<Query, U256>;
 struct Inner {
     orders: SolvableOrders,
     balances: Balances,
+    /// The block at which the balances were fetched.
+    balances_block: u64,
     auction: Auction,
+    /// Native prices that take precedence over the estimated ones.
+    price_overrides: HashMap<H160, U256>,
+    /// Traded tokens that had no native price in the last update.
+    unpriced_tokens: Vec<H160>,
+    /// When orders got evicted so that updates that started earlier don't add them back.
+    evicted_orders: HashMap<OrderUid, Instant>,
 }
 
 #[derive(Clone, Debug)]
@@ -68,24 +274,53 @@ pub struct SolvableOrders {
     pub block: u64,
 }
 
+/// A copy of the full cache state taken at one point in time for diagnostics.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheSnapshot {
+    pub orders: Vec<Order>,
+    pub balances: Vec<(Query, U256)>,
+    pub balances_block: u64,
+    pub auction: Auction,
+    pub price_overrides: HashMap<H160, U256>,
+    pub unpriced_tokens: Vec<H160>,
+    pub update_time: SystemTime,
+}
+
 impl SolvableOrdersCache {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         min_order_validity_period: Duration,
         database: Arc<dyn OrderStoring>,
         banned_users: HashSet<H160>,
+        trusted_tokens: HashSet<H160>,
+        banned_app_data: HashSet<H256>,
         balance_fetcher: Arc<dyn BalanceFetching>,
         bad_token_detector: Arc<dyn BadTokenDetecting>,
         current_block: CurrentBlockStream,
         native_price_estimator: Arc<dyn NativePriceEstimating>,
+        secondary_native_price_estimator: Option<Arc<dyn NativePriceEstimating>>,
+        native_token: H160,
         auction_metrics: Arc<dyn AuctionMetrics>,
+        config: SolvableOrdersCacheConfig,
     ) -> Arc<Self> {
+        let auction = Auction {
+            block: 0,
+            latest_settlement_block: 0,
+            orders: Default::default(),
+            prices: Default::default(),
+        };
+        let (auction_sender, _) = watch::channel(auction.clone());
         let self_ = Arc::new(Self {
             min_order_validity_period,
+            config,
             database,
-            banned_users,
+            banned_users: RwLock::new(banned_users),
+            trusted_tokens: RwLock::new(trusted_tokens),
+            banned_app_data: RwLock::new(banned_app_data),
             balance_fetcher,
             bad_token_detector,
+            current_block: current_block.clone(),
             notify: Default::default(),
             cache: Mutex::new(Inner {
                 orders: SolvableOrders {
@@ -95,60 +330,537 @@ impl SolvableOrdersCache {
                     block: 0,
                 },
                 balances: Default::default(),
-                auction: Auction {
-                    block: 0,
-                    latest_settlement_block: 0,
-                    orders: Default::default(),
-                    prices: Default::default(),
-                },
+                balances_block: 0,
+                auction,
+                price_overrides: Default::default(),
+                unpriced_tokens: Default::default(),
+                evicted_orders: Default::default(),
             }),
             native_price_estimator,
+            secondary_native_price_estimator,
+            native_token,
             auction_metrics,
+            auction_sender,
+            updates_started: AtomicU64::new(0),
+            updates_published: watch::channel(0).0,
+            shutdown: AtomicBool::new(false),
+            created: Instant::now(),
+            last_tick: AtomicU64::new(0),
+            unpriceable_tokens: Default::default(),
+            auction_history: Default::default(),
+            update_task_handle: Mutex::new(None),
         });
-        tokio::task::spawn(update_task(Arc::downgrade(&self_), current_block));
+        let handle = tokio::task::spawn(update_task(Arc::downgrade(&self_), current_block));
+        *lock(&self_.update_task_handle) = Some(handle);
         self_
     }
 
     pub fn cached_balance(&self, key: &Query) -> Option<U256> {
-        let inner = self.cache.lock().unwrap();
+        let inner = lock(&self.cache);
         inner.balances.get(key).copied()
     }
 
+    /// Fetches the current balance for the query and stores it in the cache so that subsequent
+    /// `cached_balance` calls reflect it. Does not rebuild the auction.
+    pub async fn refresh_balance(&self, query: &Query) -> Result<U256> {
+        let balance = self
+            .balance_fetcher
+            .get_balances(std::slice::from_ref(query))
+            .await
+            .into_iter()
+            .next()
+            .context("balance fetcher returned no result")??;
+        lock(&self.cache).balances.insert(*query, balance);
+        Ok(balance)
+    }
+
     /// Orders and timestamp at which last update happened.
     pub fn cached_solvable_orders(&self) -> SolvableOrders {
-        self.cache.lock().unwrap().orders.clone()
+        lock(&self.cache).orders.clone()
+    }
+
+    /// Runs the filters of an update against the current solvable orders in the database and
+    /// reports at which stage the specified order gets removed. This queries the database, the
+    /// bad token detector, balances and native prices so it is meant for debugging only.
+    pub async fn explain(&self, uid: &OrderUid) -> Result<OrderFilterReport> {
+        let min_valid_to = now_in_epoch_seconds() + self.min_order_validity_period.as_secs() as u32;
+        let orders = self.database.solvable_orders(min_valid_to).await?.orders;
+        let owner = match orders.iter().find(|order| order.metadata.uid == *uid) {
+            Some(order) => order.metadata.owner,
+            None => return Ok(OrderFilterReport::Expired),
+        };
+        // Balances and the order limit per user only depend on other orders of the same owner.
+        let orders = orders
+            .into_iter()
+            .filter(|order| order.metadata.owner == owner)
+            .collect::<Vec<_>>();
+        let removed = |orders: &[Order]| !orders.iter().any(|order| order.metadata.uid == *uid);
+
+        let orders = filter_banned_user_orders(orders, &read(&self.banned_users));
+        if removed(&orders) {
+            return Ok(OrderFilterReport::BannedUser);
+        }
+        let orders = filter_banned_app_data_orders(orders, &read(&self.banned_app_data));
+        if removed(&orders) {
+            return Ok(OrderFilterReport::BannedAppData);
+        }
+        if let Some(min_age) = self.config.min_order_age {
+            if removed(&filter_new_orders(
+                orders.clone(),
+                min_age,
+                chrono::Utc::now(),
+            )) {
+                return Ok(OrderFilterReport::TooNew);
+            }
+        }
+        let orders = filter_self_trade_orders(filter_duplicate_orders(orders));
+        if removed(&orders) {
+            return Ok(OrderFilterReport::SelfTrade);
+        }
+        let trusted_tokens = read(&self.trusted_tokens).clone();
+        let orders =
+            filter_unsupported_tokens(orders, self.bad_token_detector.as_ref(), &trusted_tokens)
+                .await?;
+        if removed(&orders) {
+            return Ok(OrderFilterReport::UnsupportedToken);
+        }
+        let order = orders
+            .iter()
+            .find(|order| order.metadata.uid == *uid)
+            .expect("order was not removed");
+        if matches!(max_transfer_out_amount(order), Ok(amount) if amount.is_zero()) {
+            return Ok(OrderFilterReport::ZeroAmount);
+        }
+
+        let (_, queries) = new_balances(&Default::default(), &orders);
+        let fetched_balances = fetch_balances(
+            self.balance_fetcher.as_ref(),
+            &queries,
+            self.config.balance_query_chunk_size,
+        )
+        .await;
+        let balances = queries
+            .into_iter()
+            .zip(fetched_balances)
+            .filter_map(|(query, balance)| Some((query, balance.ok()?)))
+            .collect();
+        // Explaining an order must not show up in the metrics of the actual auction updates.
+        let orders = solvable_orders(
+            orders,
+            &balances,
+            self.config.order_prioritization,
+            &NoopMetrics,
+        );
+        if removed(&orders) {
+            return Ok(OrderFilterReport::InsufficientBalance);
+        }
+        let orders = match self.config.max_orders_per_user {
+            Some(max_orders) => limit_orders_per_user(orders, max_orders),
+            None => orders,
+        };
+        if removed(&orders) {
+            return Ok(OrderFilterReport::UserOrderLimit);
+        }
+
+        let order = orders
+            .into_iter()
+            .find(|order| order.metadata.uid == *uid)
+            .expect("order was not removed");
+        let mut tokens = traded_tokens(std::slice::from_ref(&order));
+        tokens.retain(|token| !self.config.static_native_prices.contains_key(token));
+        let mut native_prices = get_native_prices(
+            &tokens,
+            self.native_price_estimator.as_ref(),
+            Instant::now() + MAX_AUCTION_CREATION_TIME,
+            None,
+            self.config.max_concurrent_native_price_estimates,
+        )
+        .await;
+        native_prices
+            .prices
+            .extend(self.config.static_native_prices.clone());
+        let price_overrides = lock(&self.cache).price_overrides.clone();
+        let price = |token: &H160| {
+            price_overrides
+                .get(token)
+                .or_else(|| native_prices.prices.get(token))
+                .copied()
+        };
+        let (sell_price, buy_price) = match (
+            price(&order.creation.sell_token),
+            price(&order.creation.buy_token),
+        ) {
+            (Some(sell_price), Some(buy_price)) => (sell_price, buy_price),
+            _ => return Ok(OrderFilterReport::MissingPrice),
+        };
+        if let Some(min_value) = self.config.min_order_native_value {
+            if !has_min_native_value(&order, sell_price, buy_price, min_value) {
+                return Ok(OrderFilterReport::Dust);
+            }
+        }
+        if let Some(max_deviation_bps) = self.config.max_limit_price_deviation_bps {
+            if !is_marketable(&order, sell_price, buy_price, max_deviation_bps) {
+                return Ok(OrderFilterReport::Unmarketable);
+            }
+        }
+        Ok(OrderFilterReport::Solvable)
+    }
+
+    /// Returns whether the solvable orders were last updated at most `max_age` ago.
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        lock(&self.cache).orders.update_time.elapsed() <= max_age
+    }
+
+    /// Returns when the background update task last woke up to update the solvable orders. This
+    /// keeps advancing even if the updates fail so a health check can use it to detect that the
+    /// task stopped running. Note that the task waits up to `max_update_backoff` between updates.
+    pub fn last_update_task_tick(&self) -> Instant {
+        self.created + Duration::from_millis(self.last_tick.load(Ordering::SeqCst))
+    }
+
+    /// Returns the traded tokens that had neither an estimated nor an overridden native price in
+    /// the last update.
+    pub fn last_unpriced_tokens(&self) -> Vec<H160> {
+        lock(&self.cache).unpriced_tokens.clone()
+    }
+
+    /// Returns a copy of the full cache state. The lock is only held while cloning.
+    pub fn snapshot(&self) -> CacheSnapshot {
+        let (
+            orders,
+            balances,
+            balances_block,
+            auction,
+            price_overrides,
+            unpriced_tokens,
+            update_time,
+        ) = {
+            let cache = lock(&self.cache);
+            (
+                cache.orders.orders.clone(),
+                cache.balances.clone(),
+                cache.balances_block,
+                cache.auction.clone(),
+                cache.price_overrides.clone(),
+                cache.unpriced_tokens.clone(),
+                cache.orders.update_time,
+            )
+        };
+        CacheSnapshot {
+            orders,
+            balances: balances.into_iter().collect(),
+            balances_block,
+            auction,
+            price_overrides,
+            unpriced_tokens,
+            update_time: SystemTime::now() - update_time.elapsed(),
+        }
     }
 
     // Returns auction and update time.
     pub fn cached_auction(&self) -> (Auction, Instant) {
-        let cache = self.cache.lock().unwrap();
+        let cache = lock(&self.cache);
         (cache.auction.clone(), cache.orders.update_time)
     }
 
+    /// Returns the most recent auction that was published for the specified block if it is still
+    /// part of the auction history.
+    pub fn auction_at_block(&self, block: u64) -> Option<Auction> {
+        lock(&self.auction_history)
+            .iter()
+            .rev()
+            .find(|auction| auction.block == block)
+            .cloned()
+    }
+
+    /// Returns a receiver that gets notified with every newly published auction.
+    pub fn auction_stream(&self) -> watch::Receiver<Auction> {
+        self.auction_sender.subscribe()
+    }
+
+    /// Removes the order from the solvable orders, the current auction and the auction history
+    /// right away, for example because it got cancelled. Updates that are already running when the
+    /// order gets evicted don't add it back. Later updates reconcile the cache with the database.
+    pub fn evict_order(&self, uid: &OrderUid) {
+        {
+            let mut inner = lock(&self.cache);
+            inner.evicted_orders.insert(*uid, Instant::now());
+            inner
+                .orders
+                .orders
+                .retain(|order| order.metadata.uid != *uid);
+            let auction_orders = inner.auction.orders.len();
+            inner
+                .auction
+                .orders
+                .retain(|order| order.metadata.uid != *uid);
+            if inner.auction.orders.len() < auction_orders {
+                self.auction_sender.send_replace(inner.auction.clone());
+            }
+        }
+        for auction in lock(&self.auction_history).iter_mut() {
+            auction.orders.retain(|order| order.metadata.uid != *uid);
+        }
+    }
+
+    /// Forces the native price of `token` to `price` in all following auctions, regardless of
+    /// what the native price estimator returns.
+    pub fn set_price_override(&self, token: H160, price: U256) {
+        lock(&self.cache).price_overrides.insert(token, price);
+    }
+
+    /// Removes a price override so that the estimated native price is used again.
+    pub fn remove_price_override(&self, token: &H160) {
+        lock(&self.cache).price_overrides.remove(token);
+    }
+
+    /// Replaces the set of banned users. Takes effect on the next update.
+    pub fn set_banned_users(&self, users: HashSet<H160>) {
+        *write(&self.banned_users) = users;
+    }
+
+    /// Replaces the set of banned app data hashes. Takes effect on the next update.
+    pub fn set_banned_app_data(&self, app_data: HashSet<H256>) {
+        *write(&self.banned_app_data) = app_data;
+    }
+
+    /// Replaces the set of tokens that are always considered supported regardless of the bad token
+    /// detector. Takes effect on the next update.
+    pub fn set_trusted_tokens(&self, tokens: HashSet<H160>) {
+        *write(&self.trusted_tokens) = tokens;
+    }
+
+    /// Returns the tokens that are currently always considered supported.
+    pub fn trusted_tokens(&self) -> HashSet<H160> {
+        read(&self.trusted_tokens).clone()
+    }
+
     /// The cache will update the solvable orders and missing balances as soon as possible.
     pub fn request_update(&self) {
         self.notify.notify_one();
     }
 
+    /// Requests an update and waits until an update that started after the request has published
+    /// new solvable orders. Updates that fail or keep the previous auction don't count so this
+    /// returns an error if no update got published within the timeout.
+    pub async fn request_update_and_wait(&self, timeout: Duration) -> Result<()> {
+        let mut published = self.updates_published.subscribe();
+        let update = self.updates_started.load(Ordering::SeqCst) + 1;
+        self.request_update();
+        let wait = async {
+            while *published.borrow_and_update() < update {
+                published
+                    .changed()
+                    .await
+                    .context("solvable orders update notifications closed")?;
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .with_context(|| format!("no solvable orders update published within {:?}", timeout))?
+    }
+
+    /// Fetches the balances of all solvable orders into the cache without building an auction. The
+    /// next update reuses them if it happens in the same block, which avoids fetching all balances
+    /// at once in the first update after startup.
+    pub async fn prewarm(&self) -> Result<()> {
+        let block = self
+            .current_block
+            .borrow()
+            .number
+            .context("no block number")?
+            .as_u64();
+        let min_valid_to = now_in_epoch_seconds() + self.min_order_validity_period.as_secs() as u32;
+        let orders = self.database.solvable_orders(min_valid_to).await?.orders;
+        let (_, queries) = new_balances(&Default::default(), &orders);
+        let fetched_balances = fetch_balances(
+            self.balance_fetcher.as_ref(),
+            &queries,
+            self.config.balance_query_chunk_size,
+        )
+        .await;
+        let balances = queries
+            .into_iter()
+            .zip(fetched_balances)
+            .filter_map(|(query, balance)| Some((query, balance.ok()?)))
+            .collect::<Balances>();
+        tracing::debug!(balances = balances.len(), block, "prewarmed balance cache");
+
+        let mut inner = lock(&self.cache);
+        inner.balances = balances;
+        inner.balances_block = block;
+        Ok(())
+    }
+
+    /// Stops the background update task and waits for it to exit. An update that is currently in
+    /// progress is completed first.
+    pub async fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.notify.notify_one();
+        let handle = lock(&self.update_task_handle).take();
+        if let Some(handle) = handle {
+            if let Err(err) = handle.await {
+                tracing::error!(?err, "solvable orders update task failed");
+            }
+        }
+    }
+
     /// Manually update solvable orders. Usually called by the background updating task.
-    pub async fn update(&self, block: u64) -> Result<()> {
+    pub async fn update(&self, block: u64) -> Result<(), UpdateError> {
+        let update = self.updates_started.fetch_add(1, Ordering::SeqCst) + 1;
+        let update_start = Instant::now();
         let min_valid_to = now_in_epoch_seconds() + self.min_order_validity_period.as_secs() as u32;
-        let db_solvable_orders = self.database.solvable_orders(min_valid_to).await?;
-        let orders = filter_banned_user_orders(db_solvable_orders.orders, &self.banned_users);
-        let orders = filter_unsupported_tokens(orders, self.bad_token_detector.as_ref()).await?;
+        let db_solvable_orders = self
+            .database
+            .solvable_orders(min_valid_to)
+            .await
+            .map_err(UpdateError::DatabaseError)?;
+        let mut dispositions = OrderDispositions::new(&db_solvable_orders.orders);
+        let mut record_removed = |reason, before: usize, orders: &[Order]| {
+            if before > orders.len() {
+                self.auction_metrics
+                    .orders_removed(reason, (before - orders.len()) as u64);
+            }
+            dispositions.record(reason, orders);
+        };
+        let order_count = db_solvable_orders.orders.len();
+        let orders =
+            filter_banned_user_orders(db_solvable_orders.orders, &read(&self.banned_users));
+        record_removed("banned_user", order_count, &orders);
+        let order_count = orders.len();
+        let orders = filter_banned_app_data_orders(orders, &read(&self.banned_app_data));
+        record_removed("banned_app_data", order_count, &orders);
+        let order_count = orders.len();
+        let orders = match self.config.min_order_age {
+            Some(min_age) => filter_new_orders(orders, min_age, chrono::Utc::now()),
+            None => orders,
+        };
+        record_removed("too_new", order_count, &orders);
+        let order_count = orders.len();
+        let orders = filter_duplicate_orders(orders);
+        let duplicate_count = order_count - orders.len();
+        if duplicate_count > 0 {
+            self.auction_metrics
+                .duplicate_orders_removed(duplicate_count as u64);
+        }
+        record_removed("duplicate", order_count, &orders);
+        let order_count = orders.len();
+        let orders = filter_self_trade_orders(orders);
+        let self_trade_count = order_count - orders.len();
+        if self_trade_count > 0 {
+            self.auction_metrics
+                .self_trade_orders_removed(self_trade_count as u64);
+        }
+        record_removed("self_trade", order_count, &orders);
+        let order_count = orders.len();
+        let trusted_tokens = read(&self.trusted_tokens).clone();
+        let orders =
+            filter_unsupported_tokens(orders, self.bad_token_detector.as_ref(), &trusted_tokens)
+                .await
+                .map_err(UpdateError::BadTokenDetection)?;
+        record_removed("unsupported_token", order_count, &orders);
 
-        // If we update due to an explicit notification we can reuse existing balances as they
-        // cannot have changed.
+        // If we update due to an explicit notification we can reuse existing erc20 balances as
+        // they cannot have changed. Vault balances are always fetched again.
         let old_balances = {
-            let inner = self.cache.lock().unwrap();
-            if inner.orders.block == block {
-                inner.balances.clone()
+            let inner = lock(&self.cache);
+            if inner.balances_block == block {
+                reusable_balances(&inner.balances)
             } else {
                 HashMap::new()
             }
         };
         let (mut new_balances, missing_queries) = new_balances(&old_balances, &orders);
-        let fetched_balances = self.balance_fetcher.get_balances(&missing_queries).await;
+
+        // The set of traded tokens is already known at this point, so we can estimate native
+        // prices while the missing balances are being fetched.
+        let traded_tokens = traded_tokens(&orders);
+        let known_prices = {
+            let inner = lock(&self.cache);
+            traded_tokens
+                .iter()
+                .filter(|token| inner.auction.prices.contains_key(token))
+                .count()
+        };
+        self.auction_metrics.native_price_overlap(
+            known_prices as u64,
+            (traded_tokens.len() - known_prices) as u64,
+        );
+        let (mut tokens_to_estimate, skipped_tokens) = skip_unpriceable_tokens(
+            &traded_tokens,
+            &mut lock(&self.unpriceable_tokens),
+            self.config.unpriceable_token_ttl,
+            Instant::now(),
+        );
+        if skipped_tokens > 0 {
+            self.auction_metrics
+                .native_price_estimates_skipped(skipped_tokens as u64);
+        }
+        tokens_to_estimate.retain(|token| !self.config.static_native_prices.contains_key(token));
+        let (fetched_balances, native_prices) = futures::join!(
+            fetch_balances(
+                self.balance_fetcher.as_ref(),
+                &missing_queries,
+                self.config.balance_query_chunk_size,
+            ),
+            async {
+                let deadline = Instant::now() + MAX_AUCTION_CREATION_TIME;
+                let mut native_prices = get_native_prices(
+                    &tokens_to_estimate,
+                    &*self.native_price_estimator,
+                    deadline,
+                    Some(
+                        Instant::now()
+                            + MAX_AUCTION_CREATION_TIME.mul_f64(
+                                self.config
+                                    .native_price_soft_deadline_fraction
+                                    .clamp(0., 1.),
+                            ),
+                    ),
+                    self.config.max_concurrent_native_price_estimates,
+                )
+                .await;
+                if let Some(estimator) = &self.secondary_native_price_estimator {
+                    add_fallback_native_prices(
+                        &tokens_to_estimate,
+                        &mut native_prices,
+                        estimator.as_ref(),
+                        deadline,
+                        self.config.max_concurrent_native_price_estimates,
+                    )
+                    .await;
+                }
+                native_prices
+                    .prices
+                    .extend(self.config.static_native_prices.clone());
+                native_prices
+            },
+        );
+        if !missing_queries.is_empty() && fetched_balances.iter().all(Result::is_err) {
+            let err = fetched_balances
+                .into_iter()
+                .find_map(Result::err)
+                .expect("balances are not empty");
+            return Err(UpdateError::BalanceFetchError(err));
+        }
+        // Price overrides can still let orders through so a timeout without any price is handled
+        // together with the other estimator failures once the auction is built.
+        let native_price_timeout = native_prices.timeout
+            && native_prices.prices.is_empty()
+            && !tokens_to_estimate.is_empty();
+        let native_price_estimator_down = native_prices.timeout
+            || (!tokens_to_estimate.is_empty()
+                && tokens_to_estimate
+                    .iter()
+                    .all(|token| !native_prices.prices.contains_key(token)));
+        {
+            let now = Instant::now();
+            let mut unpriceable_tokens = lock(&self.unpriceable_tokens);
+            for token in &native_prices.unpriceable_tokens {
+                unpriceable_tokens.insert(*token, now);
+            }
+        }
         for (query, balance) in missing_queries.into_iter().zip(fetched_balances) {
             let balance = match balance {
                 Ok(balance) => balance,
@@ -166,115 +878,540 @@ impl SolvableOrdersCache {
             new_balances.insert(query, balance);
         }
 
-        let mut orders = solvable_orders(orders, &new_balances);
+        let order_count = orders.len();
+        let orders = solvable_orders(
+            orders,
+            &new_balances,
+            self.config.order_prioritization,
+            self.auction_metrics.as_ref(),
+        );
+        record_removed("insufficient_balance", order_count, &orders);
+        let order_count = orders.len();
+        let mut orders = match self.config.max_orders_per_user {
+            Some(max_orders) => limit_orders_per_user(orders, max_orders),
+            None => orders,
+        };
+        record_removed("user_order_limit", order_count, &orders);
         for order in &mut orders {
             let query = Query::from_order(order);
             order.metadata.available_balance = new_balances.get(&query).copied();
         }
 
         // create auction
-        let (orders, prices) = get_orders_with_native_prices(
-            orders.clone(),
-            &*self.native_price_estimator,
-            Instant::now() + MAX_AUCTION_CREATION_TIME,
+        let unpriced_order_count = orders.len();
+        let price_overrides = lock(&self.cache).price_overrides.clone();
+        let unpriced_tokens = unpriced_tokens(&traded_tokens, &native_prices, &price_overrides);
+        let (orders, prices, removed) = get_orders_with_native_prices(
+            orders,
+            &native_prices,
+            &price_overrides,
+            self.native_token,
+            self.config.min_order_native_value,
+            self.config.max_limit_price_deviation_bps,
             self.auction_metrics.as_ref(),
-        )
-        .await;
-        let auction = Auction {
+        );
+        dispositions.record_removed(&removed);
+        self.auction_metrics
+            .balances_cached(new_balances.len() as u64);
+
+        let mut inner = lock(&self.cache);
+        // Orders evicted while this update was running can still be part of the orders fetched
+        // from the database. Earlier evictions are already reflected by the database.
+        inner
+            .evicted_orders
+            .retain(|_, evicted_at| *evicted_at >= update_start);
+        let orders = orders
+            .into_iter()
+            .filter(|order| !inner.evicted_orders.contains_key(&order.metadata.uid))
+            .collect::<Vec<_>>();
+        dispositions.record("evicted", &orders);
+        // If no order could be priced because the native price estimator timed out or could not
+        // price a single token it is most likely down. Keep serving the last good auction in that
+        // case instead of an empty one. Orders that only lack some prices still leave the auction.
+        // Without a previous auction, for example right after startup, the new auction containing
+        // the orders priced by overrides is published instead.
+        if orders.is_empty()
+            && unpriced_order_count > 0
+            && native_price_estimator_down
+            && !inner.auction.orders.is_empty()
+        {
+            tracing::warn!(
+                unpriced_order_count,
+                "no orders with native prices, keeping previous auction"
+            );
+            self.auction_metrics.auction_update_stale();
+            dispositions.record("stale_auction", &[]);
+            dispositions.log();
+            inner.balances = new_balances;
+            inner.balances_block = block;
+            inner.unpriced_tokens = unpriced_tokens;
+            return if native_price_timeout {
+                Err(UpdateError::PriceEstimationTimeout)
+            } else {
+                Ok(())
+            };
+        }
+
+        let mut auction = Auction {
             block,
             latest_settlement_block: db_solvable_orders.latest_settlement_block,
-            orders: orders.clone(),
+            orders,
             prices,
         };
+        if let Some(max_size) = self.config.max_auction_size {
+            let trimmed = trim_auction(&mut auction, max_size, self.native_token);
+            if trimmed > 0 {
+                tracing::debug!(trimmed, max_size, "trimmed auction to maximum size");
+                self.auction_metrics
+                    .orders_removed("auction_size", trimmed as u64);
+            }
+            dispositions.record("auction_size", &auction.orders);
+        }
+        dispositions.log();
+
+        let diff = auction_diff(&inner.auction.orders, &auction.orders);
+        tracing::debug!(added = ?diff.added, removed = ?diff.removed, "auction orders changed");
+        self.auction_metrics
+            .auction_orders_changed(diff.added.len() as u64, diff.removed.len() as u64);
+        let liquidity_orders = auction
+            .orders
+            .iter()
+            .filter(|order| {
+                self.config
+                    .liquidity_order_owners
+                    .contains(&order.metadata.owner)
+            })
+            .count();
+        self.auction_metrics.auction_order_types(
+            (auction.orders.len() - liquidity_orders) as u64,
+            liquidity_orders as u64,
+        );
 
-        *self.cache.lock().unwrap() = Inner {
+        *inner = Inner {
             orders: SolvableOrders {
-                orders,
+                orders: auction.orders.clone(),
                 update_time: Instant::now(),
                 latest_settlement_block: db_solvable_orders.latest_settlement_block,
                 block,
             },
             balances: new_balances,
-            auction,
+            balances_block: block,
+            auction: auction.clone(),
+            price_overrides: std::mem::take(&mut inner.price_overrides),
+            unpriced_tokens,
+            evicted_orders: std::mem::take(&mut inner.evicted_orders),
         };
+        drop(inner);
+        record_auction(
+            &mut lock(&self.auction_history),
+            auction.clone(),
+            self.config.auction_history_size,
+        );
+        self.auction_sender.send_replace(auction);
+        let published = (*self.updates_published.borrow()).max(update);
+        self.updates_published.send_replace(published);
 
         Ok(())
     }
 }
 
-/// Filters all orders whose owners are in the set of "banned" users.
-fn filter_banned_user_orders(mut orders: Vec<Order>, banned_users: &HashSet<H160>) -> Vec<Order> {
-    orders.retain(|order| !banned_users.contains(&order.metadata.owner));
-    orders
-}
+/// Removes the orders with the lowest native sell value from the auction until its JSON
+/// serialization fits into `max_size` bytes and drops the prices no other order needs anymore.
+/// Returns the number of removed orders.
+fn trim_auction(auction: &mut Auction, max_size: usize, native_token: H160) -> usize {
+    let mut size = json_size(auction);
+    if size <= max_size {
+        return 0;
+    }
 
-/// Returns existing balances and Vec of queries that need to be peformed.
-fn new_balances(old_balances: &Balances, orders: &[Order]) -> (HashMap<Query, U256>, Vec<Query>) {
-    let mut new_balances = HashMap::new();
-    let mut missing_queries = HashSet::new();
-    for order in orders {
-        let query = Query::from_order(order);
-        match old_balances.get(&query) {
-            Some(balance) => {
-                new_balances.insert(query, *balance);
-            }
-            None => {
-                missing_queries.insert(query);
-            }
+    let value = |order: &Order| {
+        auction
+            .prices
+            .get(&order.creation.sell_token)
+            .map(|price| order.creation.sell_amount.full_mul(*price))
+            .unwrap_or_default()
+    };
+    let mut by_value = (0..auction.orders.len()).collect::<Vec<_>>();
+    by_value.sort_by_cached_key(|index| value(&auction.orders[*index]));
+    let mut removed = HashSet::new();
+    for index in by_value {
+        if size <= max_size {
+            break;
         }
+        // Not counting the separating comma underestimates the saved bytes so the serialized
+        // auction is guaranteed to fit once the estimate does.
+        size = size.saturating_sub(json_size(&auction.orders[index]));
+        removed.insert(index);
     }
-    let missing_queries = Vec::from_iter(missing_queries);
-    (new_balances, missing_queries)
+
+    let mut index = 0;
+    auction.orders.retain(|_| {
+        let keep = !removed.contains(&index);
+        index += 1;
+        keep
+    });
+    let traded_tokens = auction
+        .orders
+        .iter()
+        .flat_map(|order| [order.creation.sell_token, order.creation.buy_token])
+        .collect::<HashSet<_>>();
+    auction
+        .prices
+        .retain(|token, _| *token == native_token || traded_tokens.contains(token));
+    removed.len()
 }
 
-// The order book has to make a choice for which orders to include when a user has multiple orders
-// selling the same token but not enough balance for all of them.
-// Assumes balance fetcher is already tracking all balances.
-fn solvable_orders(mut orders: Vec<Order>, balances: &Balances) -> Vec<Order> {
-    let mut orders_map = HashMap::<Query, Vec<Order>>::new();
-    orders.sort_by_key(|order| std::cmp::Reverse(order.metadata.creation_date));
-    for order in orders {
-        let key = Query::from_order(&order);
-        orders_map.entry(key).or_default().push(order);
-    }
+fn json_size(value: &impl Serialize) -> usize {
+    serde_json::to_vec(value)
+        .map(|json| json.len())
+        .unwrap_or(usize::MAX)
+}
 
-    let mut result = Vec::new();
-    for (key, orders) in orders_map {
-        let mut remaining_balance = match balances.get(&key) {
-            Some(balance) => *balance,
-            None => continue,
-        };
-        for order in orders {
-            // TODO: This is overly pessimistic for partially filled orders where the needed balance
-            // is lower. For partially fillable orders that cannot be fully filled because of the
-            // balance we could also give them as much balance as possible instead of skipping. For
-            // that we first need a way to communicate this to the solver. We could repurpose
-            // availableBalance for this.
-            let needed_balance = match max_transfer_out_amount(&order) {
-                // Should only ever happen if a partially fillable order has been filled completely
-                Ok(balance) if balance.is_zero() => continue,
-                Ok(balance) => balance,
-                Err(err) => {
-                    // This should only happen if we read bogus order data from
-                    // the database (either we allowed a bogus order to be
-                    // created or we updated a good order incorrectly), so raise
-                    // the alarm!
-                    tracing::error!(
-                        ?err,
-                        ?order,
-                        "error computing order max transfer out amount"
-                    );
-                    continue;
-                }
-            };
-            if let Some(balance) = remaining_balance.checked_sub(needed_balance) {
-                remaining_balance = balance;
-                result.push(order);
-            }
+/// The reason an update of the solvable orders failed.
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateError {
+    #[error("failed to fetch solvable orders from the database")]
+    DatabaseError(#[source] anyhow::Error),
+    #[error("failed to fetch any balance")]
+    BalanceFetchError(#[source] anyhow::Error),
+    #[error("native price estimation timed out without pricing any token")]
+    PriceEstimationTimeout,
+    #[error("failed to detect unsupported tokens")]
+    BadTokenDetection(#[source] anyhow::Error),
+}
+
+/// Tracks at which stage of an update orders get removed so that the final disposition of every
+/// order can be logged. Does nothing unless trace logging is enabled.
+struct OrderDispositions {
+    enabled: bool,
+    remaining: HashSet<OrderUid>,
+    removed: Vec<(OrderUid, &'static str)>,
+}
+
+impl OrderDispositions {
+    fn new(orders: &[Order]) -> Self {
+        let enabled = tracing::enabled!(tracing::Level::TRACE);
+        Self {
+            enabled,
+            remaining: if enabled {
+                orders.iter().map(|order| order.metadata.uid).collect()
+            } else {
+                Default::default()
+            },
+            removed: Vec::new(),
         }
     }
-    result
-}
+
+    /// Records the orders that are no longer in `orders` as removed for the reason.
+    fn record(&mut self, reason: &'static str, orders: &[Order]) {
+        if !self.enabled {
+            return;
+        }
+        let remaining = orders
+            .iter()
+            .map(|order| order.metadata.uid)
+            .collect::<HashSet<_>>();
+        self.removed.extend(
+            self.remaining
+                .difference(&remaining)
+                .map(|uid| (*uid, reason)),
+        );
+        self.remaining = remaining;
+    }
+
+    /// Records the orders as removed for their respective reason.
+    fn record_removed(&mut self, removed: &[(OrderUid, &'static str)]) {
+        if !self.enabled {
+            return;
+        }
+        for (uid, reason) in removed {
+            if self.remaining.remove(uid) {
+                self.removed.push((*uid, reason));
+            }
+        }
+    }
+
+    /// Logs one line per order stating whether it is included in the auction or why not.
+    fn log(&self) {
+        for uid in &self.remaining {
+            tracing::trace!(
+                order_uid = %uid,
+                disposition = "included",
+                reason = "",
+                "order disposition"
+            );
+        }
+        for (uid, reason) in &self.removed {
+            tracing::trace!(
+                order_uid = %uid,
+                disposition = "removed",
+                reason = *reason,
+                "order disposition"
+            );
+        }
+    }
+}
+
+/// The stage at which an order is removed when building the auction, see
+/// `SolvableOrdersCache::explain`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OrderFilterReport {
+    /// The order is not a solvable order in the database. It might have expired, been filled or
+    /// cancelled, or not exist at all.
+    Expired,
+    /// The owner or receiver of the order is banned.
+    BannedUser,
+    /// The app data of the order is banned.
+    BannedAppData,
+    /// The order was created more recently than the configured minimum order age.
+    TooNew,
+    /// The order sells and buys the same token.
+    SelfTrade,
+    /// The order trades a token the bad token detector considers unsupported.
+    UnsupportedToken,
+    /// The order has nothing left to trade.
+    ZeroAmount,
+    /// The owner does not have enough balance for this order.
+    InsufficientBalance,
+    /// The owner has more orders than the configured maximum per user.
+    UserOrderLimit,
+    /// Either token of the order could not be priced.
+    MissingPrice,
+    /// The order is worth less than the configured minimum native value.
+    Dust,
+    /// The limit price of the order is too far from the native price ratio.
+    Unmarketable,
+    /// The order passes all filters.
+    Solvable,
+}
+
+/// The uids of the orders that entered and left the auction between two updates.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct AuctionDiff {
+    pub added: HashSet<OrderUid>,
+    pub removed: HashSet<OrderUid>,
+}
+
+/// Computes which orders were added and removed going from the previous to the current auction.
+pub fn auction_diff(previous: &[Order], current: &[Order]) -> AuctionDiff {
+    let uids = |orders: &[Order]| {
+        orders
+            .iter()
+            .map(|order| order.metadata.uid)
+            .collect::<HashSet<_>>()
+    };
+    let (previous, current) = (uids(previous), uids(current));
+    AuctionDiff {
+        added: current.difference(&previous).copied().collect(),
+        removed: previous.difference(&current).copied().collect(),
+    }
+}
+
+/// Appends the auction to the history, replacing an earlier auction for the same block and
+/// dropping the oldest auctions so that at most `max_size` remain.
+fn record_auction(history: &mut VecDeque<Auction>, auction: Auction, max_size: usize) {
+    if matches!(history.back(), Some(last) if last.block == auction.block) {
+        history.pop_back();
+    }
+    history.push_back(auction);
+    while history.len() > max_size {
+        history.pop_front();
+    }
+}
+
+/// Fetches the balances in chunks of at most `chunk_size` queries. The results are in the same
+/// order as the queries.
+async fn fetch_balances(
+    balance_fetcher: &dyn BalanceFetching,
+    queries: &[Query],
+    chunk_size: usize,
+) -> Vec<Result<U256>> {
+    futures::stream::iter(queries.chunks(chunk_size.max(1)))
+        .map(|chunk| balance_fetcher.get_balances(chunk))
+        .buffered(MAX_CONCURRENT_BALANCE_REQUESTS)
+        .concat()
+        .await
+}
+
+/// Filters all orders whose owners or receivers are in the set of "banned" users.
+fn filter_banned_user_orders(mut orders: Vec<Order>, banned_users: &HashSet<H160>) -> Vec<Order> {
+    orders.retain(|order| {
+        !banned_users.contains(&order.metadata.owner)
+            && !matches!(order.creation.receiver, Some(receiver) if banned_users.contains(&receiver))
+    });
+    orders
+}
+
+/// Filters all orders whose app data is in the set of banned app data hashes.
+fn filter_banned_app_data_orders(
+    mut orders: Vec<Order>,
+    banned_app_data: &HashSet<H256>,
+) -> Vec<Order> {
+    orders.retain(|order| {
+        let banned = banned_app_data.contains(&H256(order.creation.app_data.0));
+        if banned {
+            tracing::debug!(
+                uid = %order.metadata.uid,
+                app_data = ?order.creation.app_data,
+                "dropping order with banned app data"
+            );
+        }
+        !banned
+    });
+    orders
+}
+
+/// Removes orders that were created less than `min_age` before `now`.
+fn filter_new_orders(
+    mut orders: Vec<Order>,
+    min_age: Duration,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<Order> {
+    let min_age =
+        chrono::Duration::from_std(min_age).unwrap_or_else(|_| chrono::Duration::max_value());
+    orders.retain(|order| now.signed_duration_since(order.metadata.creation_date) >= min_age);
+    orders
+}
+
+/// Removes orders whose uid already appeared earlier in the list, keeping the first occurrence.
+fn filter_duplicate_orders(mut orders: Vec<Order>) -> Vec<Order> {
+    let mut uids = HashSet::new();
+    orders.retain(|order| {
+        let is_new = uids.insert(order.metadata.uid);
+        if !is_new {
+            tracing::warn!(uid = %order.metadata.uid, "dropping duplicate order");
+        }
+        is_new
+    });
+    orders
+}
+
+/// Removes orders that sell and buy the same token.
+fn filter_self_trade_orders(mut orders: Vec<Order>) -> Vec<Order> {
+    orders.retain(|order| {
+        let is_self_trade = order.creation.sell_token == order.creation.buy_token;
+        if is_self_trade {
+            tracing::warn!(uid = %order.metadata.uid, "dropping self trade order");
+        }
+        !is_self_trade
+    });
+    orders
+}
+
+/// Returns the balances that can be reused for another update within the same block. Only balances
+/// of orders selling through direct erc20 allowances are reused. Vault balances can be changed by
+/// internal Vault transfers which are not reflected by the block.
+fn reusable_balances(balances: &Balances) -> Balances {
+    balances
+        .iter()
+        .filter(|(query, _)| query.source == SellTokenSource::Erc20)
+        .map(|(query, balance)| (*query, *balance))
+        .collect()
+}
+
+/// Returns existing balances and Vec of queries that need to be peformed.
+fn new_balances(old_balances: &Balances, orders: &[Order]) -> (HashMap<Query, U256>, Vec<Query>) {
+    let mut new_balances = HashMap::new();
+    let mut missing_queries = HashSet::new();
+    for order in orders {
+        let query = Query::from_order(order);
+        match old_balances.get(&query) {
+            Some(balance) => {
+                new_balances.insert(query, *balance);
+            }
+            None => {
+                missing_queries.insert(query);
+            }
+        }
+    }
+    let missing_queries = Vec::from_iter(missing_queries);
+    (new_balances, missing_queries)
+}
+
+// The order book has to make a choice for which orders to include when a user has multiple orders
+// selling the same token but not enough balance for all of them.
+// Assumes balance fetcher is already tracking all balances.
+fn solvable_orders(
+    mut orders: Vec<Order>,
+    balances: &Balances,
+    prioritization: OrderPrioritization,
+    metrics: &dyn AuctionMetrics,
+) -> Vec<Order> {
+    let mut orders_map = HashMap::<Query, Vec<Order>>::new();
+    orders.sort_by_key(|order| std::cmp::Reverse(order.metadata.creation_date));
+    for order in orders {
+        let key = Query::from_order(&order);
+        orders_map.entry(key).or_default().push(order);
+    }
+
+    let mut result = Vec::new();
+    for (key, mut orders) in orders_map {
+        if prioritization == OrderPrioritization::LimitPrice {
+            // Stable sort so that orders with equal limit prices keep the creation date order.
+            orders.sort_by(|a, b| {
+                let (a, b) = (&a.creation, &b.creation);
+                b.buy_amount
+                    .full_mul(a.sell_amount)
+                    .cmp(&a.buy_amount.full_mul(b.sell_amount))
+            });
+        }
+        let mut remaining_balance = match balances.get(&key) {
+            Some(balance) => *balance,
+            None => continue,
+        };
+        for order in orders {
+            // TODO: This is overly pessimistic for partially filled orders where the needed balance
+            // is lower. For partially fillable orders that cannot be fully filled because of the
+            // balance we could also give them as much balance as possible instead of skipping. For
+            // that we first need a way to communicate this to the solver. We could repurpose
+            // availableBalance for this.
+            let needed_balance = match max_transfer_out_amount(&order) {
+                // Should only ever happen if a partially fillable order has been filled completely
+                Ok(balance) if balance.is_zero() => continue,
+                Ok(balance) => balance,
+                Err(err) => {
+                    // This should only happen if we read bogus order data from
+                    // the database (either we allowed a bogus order to be
+                    // created or we updated a good order incorrectly) or for
+                    // orders whose sell and fee amount don't fit into a uint.
+                    // Neither can ever be settled, so raise the alarm!
+                    tracing::error!(
+                        ?err,
+                        ?order,
+                        "error computing order max transfer out amount"
+                    );
+                    metrics.max_transfer_out_amount_failed(order.creation.kind);
+                    continue;
+                }
+            };
+            if let Some(balance) = remaining_balance.checked_sub(needed_balance) {
+                remaining_balance = balance;
+                result.push(order);
+            }
+        }
+    }
+    result
+}
+
+/// Keeps at most `max_orders` of the most recent orders of every owner.
+fn limit_orders_per_user(mut orders: Vec<Order>, max_orders: usize) -> Vec<Order> {
+    orders.sort_by_key(|order| std::cmp::Reverse(order.metadata.creation_date));
+    let mut orders_per_user = HashMap::<H160, usize>::new();
+    orders.retain(|order| {
+        let count = orders_per_user.entry(order.metadata.owner).or_default();
+        if *count >= max_orders {
+            tracing::debug!(
+                order_uid = ?order.metadata.uid,
+                owner = ?order.metadata.owner,
+                "filtered order because owner exceeds the maximum number of orders"
+            );
+            return false;
+        }
+        *count += 1;
+        true
+    });
+    orders
+}
 
 /// Computes the maximum amount that can be transferred out for a given order.
 ///
@@ -292,29 +1429,45 @@ fn max_transfer_out_amount(order: &Order) -> Result<U256> {
 }
 
 /// Keep updating the cache every N seconds or when an update notification happens.
-/// Exits when this becomes the only reference to the cache.
+/// Exits when this becomes the only reference to the cache or when the cache is shut down.
 async fn update_task(cache: Weak<SolvableOrdersCache>, current_block: CurrentBlockStream) {
+    // We are not updating on block changes because
+    // - the state of orders could change even when the block does not like when an order
+    //   gets cancelled off chain
+    // - the event updater takes some time to run and if we go first we would not update the
+    //   orders with the most recent events.
+    const UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+    let mut consecutive_failures = 0;
     loop {
         let cache = match cache.upgrade() {
-            Some(self_) => self_,
-            None => {
+            Some(self_) if !self_.shutdown.load(Ordering::SeqCst) => self_,
+            _ => {
                 tracing::debug!("exiting solvable orders update task");
                 break;
             }
         };
         {
-            // We are not updating on block changes because
-            // - the state of orders could change even when the block does not like when an order
-            //   gets cancelled off chain
-            // - the event updater takes some time to run and if we go first we would not update the
-            //   orders with the most recent events.
-            const UPDATE_INTERVAL: Duration = Duration::from_secs(2);
-            let timeout = tokio::time::sleep(UPDATE_INTERVAL);
+            let interval = backoff_interval(
+                UPDATE_INTERVAL,
+                cache.config.max_update_backoff,
+                consecutive_failures,
+            );
+            let timeout = tokio::time::sleep(jittered_interval(
+                interval,
+                cache.config.update_interval_jitter,
+            ));
             let notified = cache.notify.notified();
             futures::pin_mut!(timeout);
             futures::pin_mut!(notified);
             futures::future::select(timeout, notified).await;
         }
+        if cache.shutdown.load(Ordering::SeqCst) {
+            tracing::debug!("exiting solvable orders update task");
+            break;
+        }
+        cache
+            .last_tick
+            .store(cache.created.elapsed().as_millis() as u64, Ordering::SeqCst);
         let block = match current_block.borrow().number {
             Some(block) => block.as_u64(),
             None => {
@@ -323,20 +1476,80 @@ async fn update_task(cache: Weak<SolvableOrdersCache>, current_block: CurrentBlo
             }
         };
         let start = Instant::now();
-        match cache.update(block).await {
-            Ok(()) => tracing::debug!(
-                "updated solvable orders in {}s",
-                start.elapsed().as_secs_f32()
-            ),
-            Err(err) => tracing::error!(
-                ?err,
-                "failed to update solvable orders in {}s",
-                start.elapsed().as_secs_f32()
-            ),
+        // A panicking update must not end the task because the cache would silently keep serving
+        // the last auction forever.
+        match AssertUnwindSafe(cache.update(block)).catch_unwind().await {
+            Err(panic) => {
+                consecutive_failures += 1;
+                cache.auction_metrics.auction_update_panicked();
+                tracing::error!(
+                    panic = ?panic_message(&*panic),
+                    "updating solvable orders panicked"
+                );
+            }
+            Ok(Ok(())) => {
+                consecutive_failures = 0;
+                let elapsed = start.elapsed();
+                cache.auction_metrics.auction_update_duration(elapsed);
+                tracing::debug!("updated solvable orders in {}s", elapsed.as_secs_f32())
+            }
+            Ok(Err(err)) => {
+                consecutive_failures += 1;
+                cache.auction_metrics.auction_update_failed();
+                tracing::error!(
+                    ?err,
+                    "failed to update solvable orders in {}s",
+                    start.elapsed().as_secs_f32()
+                )
+            }
         }
     }
 }
 
+/// Locks the mutex even if it got poisoned. An update that panics while holding one of the locks
+/// of the cache poisons it, but the background update task keeps running and the next successful
+/// update replaces the state.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Like `lock` but acquires shared access to the `RwLock`.
+fn read<T>(rwlock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    rwlock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Like `lock` but acquires exclusive access to the `RwLock`.
+fn write<T>(rwlock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    rwlock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the message of a caught panic if it has one.
+fn panic_message(panic: &(dyn Any + Send)) -> Option<&str> {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+}
+
+/// Returns the time to wait before the next update: `interval` doubled for every consecutive
+/// failure but at most `max` (and never less than `interval`).
+fn backoff_interval(interval: Duration, max: Duration, consecutive_failures: u32) -> Duration {
+    interval
+        .checked_mul(2u32.saturating_pow(consecutive_failures))
+        .unwrap_or(Duration::MAX)
+        .min(max.max(interval))
+}
+
+/// Returns `interval` randomly scaled by a factor in `[1 - jitter, 1 + jitter]`.
+fn jittered_interval(interval: Duration, jitter: f64) -> Duration {
+    let jitter = jitter.clamp(0., 1.);
+    if jitter == 0. {
+        return interval;
+    }
+    let factor = rand::thread_rng().gen_range(1. - jitter..=1. + jitter);
+    interval.mul_f64(factor)
+}
+
 #[async_trait::async_trait]
 impl Maintaining for SolvableOrdersCache {
     async fn run_maintenance(&self) -> Result<()> {
@@ -345,28 +1558,110 @@ impl Maintaining for SolvableOrdersCache {
     }
 }
 
-async fn get_orders_with_native_prices(
-    mut orders: Vec<Order>,
-    native_price_estimator: &dyn NativePriceEstimating,
-    deadline: Instant,
-    metrics: &dyn AuctionMetrics,
-) -> (Vec<Order>, BTreeMap<H160, U256>) {
-    let traded_tokens = orders
+/// Returns the deduplicated set of tokens that are bought or sold by the orders.
+fn traded_tokens(orders: &[Order]) -> Vec<H160> {
+    orders
         .iter()
         .flat_map(|order| [order.creation.sell_token, order.creation.buy_token])
         .collect::<HashSet<_>>()
         .into_iter()
+        .collect()
+}
+
+/// Returns the tokens that have neither an estimated nor an overridden native price, sorted.
+fn unpriced_tokens(
+    tokens: &[H160],
+    native_prices: &NativePrices,
+    price_overrides: &HashMap<H160, U256>,
+) -> Vec<H160> {
+    let mut unpriced_tokens = tokens
+        .iter()
+        .filter(|token| {
+            !native_prices.prices.contains_key(token) && !price_overrides.contains_key(token)
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    unpriced_tokens.sort_unstable();
+    unpriced_tokens
+}
+
+/// The native prices that could be collected for an auction.
+#[derive(Debug, Default)]
+struct NativePrices {
+    prices: HashMap<H160, U256>,
+    errored_estimates: u64,
+    /// Tokens for which the estimator reported that they cannot be priced at all.
+    unpriceable_tokens: Vec<H160>,
+    timeout: bool,
+    /// Whether the estimates were still not done at the soft deadline.
+    soft_timeout: bool,
+    /// The number of prices that came from the secondary estimator.
+    fallback_prices: u64,
+}
+
+/// Splits off the tokens that could not be priced within the last `ttl` so that they don't get
+/// estimated again. Also removes expired entries. Returns the tokens that should be estimated and
+/// the number of skipped tokens.
+fn skip_unpriceable_tokens(
+    tokens: &[H160],
+    unpriceable_tokens: &mut HashMap<H160, Instant>,
+    ttl: Duration,
+    now: Instant,
+) -> (Vec<H160>, usize) {
+    unpriceable_tokens.retain(|_, failed_at| now.saturating_duration_since(*failed_at) < ttl);
+    let tokens_to_estimate = tokens
+        .iter()
+        .filter(|token| !unpriceable_tokens.contains_key(token))
+        .copied()
         .collect::<Vec<_>>();
+    let skipped = tokens.len() - tokens_to_estimate.len();
+    (tokens_to_estimate, skipped)
+}
+
+/// Estimates normalized native prices for the tokens until the estimates are done or the
+/// deadline is reached in which case the prices collected so far are returned. A warning is logged
+/// if the estimates are not done by the soft deadline. At most `max_concurrent_estimates` tokens
+/// are estimated at the same time.
+async fn get_native_prices(
+    tokens: &[H160],
+    native_price_estimator: &dyn NativePriceEstimating,
+    deadline: Instant,
+    soft_deadline: Option<Instant>,
+    max_concurrent_estimates: Option<usize>,
+) -> NativePrices {
     let mut prices = HashMap::new();
-    let mut price_stream = native_price_estimator.estimate_native_prices(&traded_tokens);
     let mut errored_estimates: u64 = 0;
+    let mut unpriceable_tokens = Vec::new();
+    let mut price_stream = match max_concurrent_estimates {
+        Some(max_concurrent_estimates) => futures::stream::iter(tokens)
+            .map(|token| async move {
+                let (_, result) = native_price_estimator
+                    .estimate_native_prices(std::slice::from_ref(token))
+                    .next()
+                    .await?;
+                Some((token, result))
+            })
+            .buffer_unordered(max_concurrent_estimates.max(1))
+            .filter_map(futures::future::ready)
+            .boxed(),
+        None => native_price_estimator
+            .estimate_native_prices(tokens)
+            .map(|(index, result)| (&tokens[index], result))
+            .boxed(),
+    };
     let collect_prices = async {
-        while let Some((index, result)) = price_stream.next().await {
-            let token = &traded_tokens[index];
+        while let Some((token, result)) = price_stream.next().await {
             let price = match result {
                 Ok(price) => price,
                 Err(err) => {
                     errored_estimates += 1;
+                    if matches!(
+                        err,
+                        PriceEstimationError::NoLiquidity
+                            | PriceEstimationError::UnsupportedToken(_)
+                    ) {
+                        unpriceable_tokens.push(*token);
+                    }
                     tracing::warn!(?token, ?err, "error estimating native token price");
                     continue;
                 }
@@ -378,26 +1673,140 @@ async fn get_orders_with_native_prices(
             prices.insert(*token, price);
         }
     };
+    let mut soft_timeout = false;
+    let soft_deadline_warning = async {
+        if let Some(soft_deadline) = soft_deadline {
+            tokio::time::sleep_until(soft_deadline).await;
+            tracing::warn!(
+                tokens = tokens.len(),
+                "auction native price collection is approaching the deadline",
+            );
+            soft_timeout = true;
+        }
+        futures::future::pending::<()>().await
+    };
+    let collect_prices = async {
+        futures::pin_mut!(collect_prices);
+        futures::pin_mut!(soft_deadline_warning);
+        futures::future::select(collect_prices, soft_deadline_warning).await;
+    };
     let timeout = match tokio::time::timeout_at(deadline, collect_prices).await {
         Ok(()) => false,
         Err(_) => {
             tracing::warn!(
-                "auction native price collection took too long, got {} out of {}",
-                prices.len(),
-                traded_tokens.len()
+                priced_tokens = prices.len(),
+                tokens = tokens.len(),
+                "auction native price collection took too long",
             );
             true
         }
     };
 
+    NativePrices {
+        prices,
+        errored_estimates,
+        unpriceable_tokens,
+        timeout,
+        soft_timeout,
+        fallback_prices: 0,
+    }
+}
+
+/// Estimates the tokens that have no native price yet with the secondary estimator and adds the
+/// results. Does nothing if the primary estimator already ran into the deadline.
+async fn add_fallback_native_prices(
+    tokens: &[H160],
+    native_prices: &mut NativePrices,
+    native_price_estimator: &dyn NativePriceEstimating,
+    deadline: Instant,
+    max_concurrent_estimates: Option<usize>,
+) {
+    if native_prices.timeout {
+        return;
+    }
+    let missing_tokens = tokens
+        .iter()
+        .filter(|token| !native_prices.prices.contains_key(token))
+        .copied()
+        .collect::<Vec<_>>();
+    if missing_tokens.is_empty() {
+        return;
+    }
+    let fallback = get_native_prices(
+        &missing_tokens,
+        native_price_estimator,
+        deadline,
+        None,
+        max_concurrent_estimates,
+    )
+    .await;
+    tracing::debug!(
+        missing = missing_tokens.len(),
+        priced = fallback.prices.len(),
+        "estimated missing native prices with secondary estimator"
+    );
+    native_prices
+        .unpriceable_tokens
+        .retain(|token| !fallback.prices.contains_key(token));
+    native_prices.timeout |= fallback.timeout;
+    native_prices.fallback_prices += fallback.prices.len() as u64;
+    native_prices.prices.extend(fallback.prices);
+}
+
+/// Keeps the orders that have native prices for both traded tokens and pass the native value and
+/// marketability checks. Returns them together with the used prices and the removed orders with
+/// the reason of their removal.
+fn get_orders_with_native_prices(
+    mut orders: Vec<Order>,
+    native_prices: &NativePrices,
+    price_overrides: &HashMap<H160, U256>,
+    native_token: H160,
+    min_order_native_value: Option<U256>,
+    max_limit_price_deviation_bps: Option<u32>,
+    metrics: &dyn AuctionMetrics,
+) -> (
+    Vec<Order>,
+    BTreeMap<H160, U256>,
+    Vec<(OrderUid, &'static str)>,
+) {
+    let price = |token: &H160| {
+        price_overrides
+            .get(token)
+            .or_else(|| native_prices.prices.get(token))
+    };
     let original_order_count = orders.len() as u64;
     // Filter both orders and prices so that we only return orders that have prices and prices that
-    // have orders.
+    // have orders. The native token is the exception as solvers always need its price.
     let mut used_prices = BTreeMap::new();
+    let mut dust_orders = 0u64;
+    let mut unmarketable_orders = 0u64;
+    let mut removed = Vec::new();
     orders.retain(|order| {
         let (t0, t1) = (&order.creation.sell_token, &order.creation.buy_token);
-        match (prices.get(t0), prices.get(t1)) {
+        match (price(t0), price(t1)) {
             (Some(p0), Some(p1)) => {
+                if let Some(min_value) = min_order_native_value {
+                    if !has_min_native_value(order, *p0, *p1, min_value) {
+                        tracing::debug!(
+                            order_uid = ?order.metadata.uid,
+                            "filtered order because of insufficient native value",
+                        );
+                        dust_orders += 1;
+                        removed.push((order.metadata.uid, "dust"));
+                        return false;
+                    }
+                }
+                if let Some(max_deviation_bps) = max_limit_price_deviation_bps {
+                    if !is_marketable(order, *p0, *p1, max_deviation_bps) {
+                        tracing::debug!(
+                            order_uid = ?order.metadata.uid,
+                            "filtered order because its limit price is too far from the market",
+                        );
+                        unmarketable_orders += 1;
+                        removed.push((order.metadata.uid, "unmarketable"));
+                        return false;
+                    }
+                }
                 used_prices.insert(*t0, *p0);
                 used_prices.insert(*t1, *p1);
                 true
@@ -407,22 +1816,84 @@ async fn get_orders_with_native_prices(
                     order_uid = ?order.metadata.uid,
                     "filtered order because of missing native token price",
                 );
+                removed.push((order.metadata.uid, "missing_native_price"));
                 false
             }
         }
     });
+    used_prices.entry(native_token).or_insert_with(|| {
+        price(&native_token)
+            .copied()
+            .unwrap_or_else(native_token_price)
+    });
 
     let solvable_orders = orders.len() as u64;
-    let filtered_orders = original_order_count - solvable_orders;
-    metrics.auction_updated(solvable_orders, filtered_orders, errored_estimates, timeout);
+    let filtered_orders =
+        original_order_count - solvable_orders - dust_orders - unmarketable_orders;
+    if dust_orders > 0 {
+        metrics.orders_removed("dust", dust_orders);
+    }
+    if unmarketable_orders > 0 {
+        metrics.orders_removed("unmarketable", unmarketable_orders);
+    }
+    if filtered_orders > 0 {
+        metrics.orders_removed("missing_price", filtered_orders);
+    }
+    if native_prices.soft_timeout {
+        metrics.native_price_estimation_slow();
+    }
+    if native_prices.fallback_prices > 0 {
+        metrics.native_prices_from_fallback(native_prices.fallback_prices);
+    }
+    metrics.auction_updated(
+        solvable_orders,
+        filtered_orders,
+        native_prices.errored_estimates,
+        native_prices.timeout,
+    );
+
+    (orders, used_prices, removed)
+}
+
+/// Returns whether either the sell or the buy amount of the order is worth at least `min_value`
+/// wei. The prices are native prices normalized to `NATIVE_PRICE_SCALE`.
+fn has_min_native_value(order: &Order, sell_price: U256, buy_price: U256, min_value: U256) -> bool {
+    let min_value = min_value.full_mul(U256::from(NATIVE_PRICE_SCALE));
+    order.creation.sell_amount.full_mul(sell_price) >= min_value
+        || order.creation.buy_amount.full_mul(buy_price) >= min_value
+}
+
+/// Returns whether the limit price of the order is at most `max_deviation_bps` basis points worse
+/// than the exchange rate implied by the native prices of the sell and buy token.
+fn is_marketable(order: &Order, sell_price: U256, buy_price: U256, max_deviation_bps: u32) -> bool {
+    // The order is marketable if
+    // `buy_amount / sell_amount <= sell_price / buy_price * (1 + bps / 10000)`.
+    let requested = order
+        .creation
+        .buy_amount
+        .full_mul(buy_price)
+        .checked_mul(U512::from(10_000));
+    let market = order
+        .creation
+        .sell_amount
+        .full_mul(sell_price)
+        .checked_mul(U512::from(10_000 + max_deviation_bps as u64));
+    match (requested, market) {
+        (Some(requested), Some(market)) => requested <= market,
+        // Amounts this large can't be compared, keep the order to be safe.
+        _ => true,
+    }
+}
 
-    (orders, used_prices)
+/// The normalized price of the native token, which is 1 in terms of itself.
+fn native_token_price() -> U256 {
+    U256::from(NATIVE_PRICE_SCALE)
 }
 
 fn to_normalized_price(price: f64) -> Option<U256> {
     let uint_max = 2.0_f64.powi(256);
 
-    let price_in_eth = 1e18 * price;
+    let price_in_eth = NATIVE_PRICE_SCALE as f64 * price;
     if price_in_eth.is_normal() && price_in_eth >= 1. && price_in_eth < uint_max {
         Some(U256::from_f64_lossy(price_in_eth))
     } else {
@@ -435,15 +1906,112 @@ mod tests {
     use super::*;
     use crate::{
         account_balances::MockBalanceFetching, database::orders::MockOrderStoring,
-        database::orders::SolvableOrders as DbOrders, metrics::NoopMetrics,
+        database::orders::SolvableOrders as DbOrders,
     };
     use chrono::{DateTime, NaiveDateTime, Utc};
     use futures::StreamExt;
     use maplit::{btreemap, hashmap, hashset};
-    use model::order::{OrderBuilder, OrderCreation, OrderKind, OrderMetadata, SellTokenSource};
+    use model::order::{
+        OrderBuilder, OrderCreation, OrderKind, OrderMetadata, OrderUid, SellTokenSource,
+    };
     use primitive_types::H160;
     use shared::price_estimation::{native::MockNativePriceEstimating, PriceEstimationError};
 
+    const NATIVE_TOKEN: H160 = H160([0xee; 20]);
+
+    /// The arguments of `SolvableOrdersCache::new` with named fields. Components that aren't set
+    /// are mocks without expectations.
+    struct CacheBuilder {
+        min_order_validity_period: Duration,
+        database: Arc<dyn OrderStoring>,
+        banned_users: HashSet<H160>,
+        trusted_tokens: HashSet<H160>,
+        banned_app_data: HashSet<H256>,
+        balance_fetcher: Arc<dyn BalanceFetching>,
+        bad_token_detector: Arc<dyn BadTokenDetecting>,
+        current_block: CurrentBlockStream,
+        native_price_estimator: Arc<dyn NativePriceEstimating>,
+        secondary_native_price_estimator: Option<Arc<dyn NativePriceEstimating>>,
+        native_token: H160,
+        metrics: Arc<dyn AuctionMetrics>,
+        config: SolvableOrdersCacheConfig,
+    }
+
+    impl Default for CacheBuilder {
+        fn default() -> Self {
+            Self {
+                min_order_validity_period: Duration::from_secs(0),
+                database: Arc::new(MockOrderStoring::new()),
+                banned_users: Default::default(),
+                trusted_tokens: Default::default(),
+                banned_app_data: Default::default(),
+                balance_fetcher: Arc::new(MockBalanceFetching::new()),
+                bad_token_detector: Arc::new(
+                    shared::bad_token::list_based::ListBasedDetector::deny_list(Vec::new()),
+                ),
+                current_block: tokio::sync::watch::channel(Default::default()).1,
+                native_price_estimator: Arc::new(MockNativePriceEstimating::new()),
+                secondary_native_price_estimator: None,
+                native_token: NATIVE_TOKEN,
+                metrics: Arc::new(NoopMetrics),
+                config: Default::default(),
+            }
+        }
+    }
+
+    impl CacheBuilder {
+        fn build(self) -> Arc<SolvableOrdersCache> {
+            SolvableOrdersCache::new(
+                self.min_order_validity_period,
+                self.database,
+                self.banned_users,
+                self.trusted_tokens,
+                self.banned_app_data,
+                self.balance_fetcher,
+                self.bad_token_detector,
+                self.current_block,
+                self.native_price_estimator,
+                self.secondary_native_price_estimator,
+                self.native_token,
+                self.metrics,
+                self.config,
+            )
+        }
+    }
+
+    #[test]
+    fn keeps_contract_orders_with_sufficient_balance() {
+        let (contract, token) = (H160([0x42; 20]), H160::from_low_u64_be(1));
+        let order = OrderBuilder::default()
+            .with_sell_token(token)
+            .with_buy_token(H160::from_low_u64_be(2))
+            .with_sell_amount(10.into())
+            .with_buy_amount(1.into())
+            .with_fee_amount(1.into())
+            .with_presign(contract)
+            .build();
+
+        let query = Query::from_order(&order);
+        assert_eq!(
+            query,
+            Query {
+                owner: contract,
+                token,
+                source: SellTokenSource::Erc20,
+            }
+        );
+        let balances = hashmap! {query => U256::from(11)};
+        assert_eq!(
+            solvable_orders(
+                vec![order.clone()],
+                &balances,
+                Default::default(),
+                &NoopMetrics
+            ),
+            [order]
+        );
+    }
+
     #[tokio::test]
     async fn filters_insufficient_balances() {
         let mut orders = vec![
@@ -472,21 +2040,86 @@ mod tests {
         ];
 
         let balances = hashmap! {Query::from_order(&orders[0]) => U256::from(9)};
-        let orders_ = solvable_orders(orders.clone(), &balances);
+        let orders_ = solvable_orders(orders.clone(), &balances, Default::default(), &NoopMetrics);
         // Second order has lower timestamp so it isn't picked.
         assert_eq!(orders_, orders[..1]);
         orders[1].metadata.creation_date =
             DateTime::from_utc(NaiveDateTime::from_timestamp(3, 0), Utc);
-        let orders_ = solvable_orders(orders.clone(), &balances);
+        let orders_ = solvable_orders(orders.clone(), &balances, Default::default(), &NoopMetrics);
         assert_eq!(orders_, orders[1..]);
     }
 
-    #[tokio::test]
-    async fn caches_orders_and_balances() {
-        let mut balance_fetcher = MockBalanceFetching::new();
-        let mut order_storing = MockOrderStoring::new();
-        let (_, receiver) = tokio::sync::watch::channel(Default::default());
-        let bad_token_detector =
+    #[test]
+    fn prioritizes_orders_by_limit_price() {
+        let order = |sell_amount: u8, buy_amount: u8, timestamp: i64| Order {
+            creation: OrderCreation {
+                sell_amount: sell_amount.into(),
+                buy_amount: buy_amount.into(),
+                ..Default::default()
+            },
+            metadata: OrderMetadata {
+                creation_date: DateTime::from_utc(NaiveDateTime::from_timestamp(timestamp, 0), Utc),
+                ..Default::default()
+            },
+        };
+        let orders = vec![order(2, 1, 2), order(2, 3, 1), order(2, 2, 0)];
+        let balances = hashmap! {Query::from_order(&orders[0]) => U256::from(4)};
+
+        // The most recent order is picked first which leaves enough balance for one more.
+        let orders_ = solvable_orders(
+            orders.clone(),
+            &balances,
+            OrderPrioritization::CreationDate,
+            &NoopMetrics,
+        );
+        assert_eq!(orders_, [orders[0].clone(), orders[1].clone()]);
+
+        let orders_ = solvable_orders(
+            orders.clone(),
+            &balances,
+            OrderPrioritization::LimitPrice,
+            &NoopMetrics,
+        );
+        assert_eq!(orders_, [orders[1].clone(), orders[2].clone()]);
+    }
+
+    #[test]
+    fn refetches_vault_balances_within_the_same_block() {
+        let order = |source| Order {
+            creation: OrderCreation {
+                sell_token_balance: source,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let orders = [
+            order(SellTokenSource::Erc20),
+            order(SellTokenSource::Internal),
+            order(SellTokenSource::External),
+        ];
+        let balances = orders
+            .iter()
+            .map(|order| (Query::from_order(order), U256::from(1)))
+            .collect();
+
+        let (new_balances, mut missing_queries) =
+            new_balances(&reusable_balances(&balances), &orders);
+        assert_eq!(
+            new_balances,
+            hashmap! { Query::from_order(&orders[0]) => U256::from(1) }
+        );
+        missing_queries.sort_by_key(|query| query.source as u8);
+        assert_eq!(
+            missing_queries,
+            [Query::from_order(&orders[1]), Query::from_order(&orders[2])]
+        );
+    }
+
+    #[tokio::test]
+    async fn caches_orders_and_balances() {
+        let mut balance_fetcher = MockBalanceFetching::new();
+        let mut order_storing = MockOrderStoring::new();
+        let bad_token_detector =
             shared::bad_token::list_based::ListBasedDetector::deny_list(Vec::new());
 
         let owner = H160::from_low_u64_le(0);
@@ -504,6 +2137,7 @@ mod tests {
                 },
                 metadata: OrderMetadata {
                     owner,
+                    uid: OrderUid([0; 56]),
                     ..Default::default()
                 },
             },
@@ -517,6 +2151,7 @@ mod tests {
                 },
                 metadata: OrderMetadata {
                     owner,
+                    uid: OrderUid([1; 56]),
                     ..Default::default()
                 },
             },
@@ -574,16 +2209,14 @@ mod tests {
             futures::stream::iter(std::iter::repeat(Ok(1.0)).take(a.len()).enumerate()).boxed()
         });
 
-        let cache = SolvableOrdersCache::new(
-            Duration::from_secs(0),
-            Arc::new(order_storing),
-            Default::default(),
-            Arc::new(balance_fetcher),
-            Arc::new(bad_token_detector),
-            receiver,
-            Arc::new(native),
-            Arc::new(NoopMetrics),
-        );
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            bad_token_detector: Arc::new(bad_token_detector),
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
 
         cache.update(0).await.unwrap();
         assert_eq!(
@@ -620,6 +2253,520 @@ mod tests {
         assert_eq!(auction.orders.len(), 0);
     }
 
+    #[tokio::test]
+    async fn fetches_balances_in_chunks() {
+        let queries = (0..5)
+            .map(|i| Query {
+                owner: H160::from_low_u64_be(i),
+                token: H160::from_low_u64_be(i),
+                source: SellTokenSource::Erc20,
+            })
+            .collect::<Vec<_>>();
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .times(3)
+            .withf(|queries| queries.len() <= 2)
+            .returning(|queries| {
+                queries
+                    .iter()
+                    .map(|query| Ok(query.owner.to_low_u64_be().into()))
+                    .collect()
+            });
+
+        let balances = fetch_balances(&balance_fetcher, &queries, 2).await;
+        let balances = balances
+            .into_iter()
+            .map(|balance| balance.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(balances, (0..5).map(U256::from).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn refreshes_single_balance() {
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .times(1)
+            .return_once(|queries| {
+                assert_eq!(queries.len(), 1);
+                vec![Ok(42.into())]
+            });
+        let cache = CacheBuilder {
+            balance_fetcher: Arc::new(balance_fetcher),
+            ..Default::default()
+        }
+        .build();
+
+        let query = Query {
+            owner: H160([1; 20]),
+            token: H160([2; 20]),
+            source: SellTokenSource::Erc20,
+        };
+        assert_eq!(cache.cached_balance(&query), None);
+        assert_eq!(cache.refresh_balance(&query).await.unwrap(), 42.into());
+        assert_eq!(cache.cached_balance(&query), Some(42.into()));
+    }
+
+    #[test]
+    fn limits_orders_per_user() {
+        let order = |owner: u8, timestamp: i64| Order {
+            metadata: OrderMetadata {
+                owner: H160([owner; 20]),
+                creation_date: DateTime::from_utc(NaiveDateTime::from_timestamp(timestamp, 0), Utc),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let orders = vec![
+            order(1, 0),
+            order(1, 2),
+            order(2, 0),
+            order(1, 1),
+            order(2, 1),
+        ];
+
+        let mut filtered = limit_orders_per_user(orders.clone(), 2)
+            .into_iter()
+            .map(|order| (order.metadata.owner, order.metadata.creation_date))
+            .collect::<Vec<_>>();
+        filtered.sort();
+        let mut expected = [&orders[1], &orders[2], &orders[3], &orders[4]]
+            .iter()
+            .map(|order| (order.metadata.owner, order.metadata.creation_date))
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(filtered, expected);
+
+        assert_eq!(limit_orders_per_user(orders, 0), []);
+    }
+
+    #[tokio::test]
+    async fn keeps_previous_auction_on_price_estimator_outage() {
+        let mut order_storing = MockOrderStoring::new();
+        let order = OrderBuilder::default()
+            .with_sell_token(H160([1; 20]))
+            .with_buy_token(H160([2; 20]))
+            .with_sell_amount(1.into())
+            .with_buy_amount(1.into())
+            .build();
+        order_storing.expect_solvable_orders().returning({
+            let order = order.clone();
+            move |_| {
+                Ok(DbOrders {
+                    orders: vec![order.clone()],
+                    latest_settlement_block: 0,
+                })
+            }
+        });
+
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+
+        let mut native = MockNativePriceEstimating::new();
+        native
+            .expect_estimate_native_prices()
+            .times(1)
+            .returning(|tokens| {
+                futures::stream::iter(std::iter::repeat(Ok(1.0)).take(tokens.len()).enumerate())
+                    .boxed()
+            });
+        native
+            .expect_estimate_native_prices()
+            .times(1)
+            .returning(|tokens| {
+                futures::stream::iter(
+                    std::iter::repeat_with(|| Err(PriceEstimationError::NoLiquidity))
+                        .take(tokens.len())
+                        .enumerate(),
+                )
+                .boxed()
+            });
+
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
+
+        cache.update(0).await.unwrap();
+        let auction = cache.cached_auction().0;
+        assert_eq!(auction.orders.len(), 1);
+
+        cache.update(1).await.unwrap();
+        assert_eq!(cache.cached_auction().0, auction);
+    }
+
+    #[tokio::test]
+    async fn does_not_keep_previous_auction_on_partial_prices() {
+        let mut order_storing = MockOrderStoring::new();
+        let sell_token = H160([1; 20]);
+        let order = OrderBuilder::default()
+            .with_sell_token(sell_token)
+            .with_buy_token(H160([2; 20]))
+            .with_sell_amount(1.into())
+            .with_buy_amount(1.into())
+            .build();
+        order_storing.expect_solvable_orders().returning({
+            let order = order.clone();
+            move |_| {
+                Ok(DbOrders {
+                    orders: vec![order.clone()],
+                    latest_settlement_block: 0,
+                })
+            }
+        });
+
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+
+        // The second update only prices the sell token so the order can't be priced anymore even
+        // though the estimator works.
+        let mut native = MockNativePriceEstimating::new();
+        native
+            .expect_estimate_native_prices()
+            .times(1)
+            .returning(|tokens| {
+                futures::stream::iter(std::iter::repeat(Ok(1.0)).take(tokens.len()).enumerate())
+                    .boxed()
+            });
+        native
+            .expect_estimate_native_prices()
+            .times(1)
+            .returning(move |tokens| {
+                let results = tokens
+                    .iter()
+                    .map(|token| {
+                        if *token == sell_token {
+                            Ok(1.0)
+                        } else {
+                            Err(PriceEstimationError::NoLiquidity)
+                        }
+                    })
+                    .enumerate()
+                    .collect::<Vec<_>>();
+                futures::stream::iter(results).boxed()
+            });
+
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
+
+        cache.update(0).await.unwrap();
+        assert_eq!(cache.cached_auction().0.orders.len(), 1);
+
+        cache.update(1).await.unwrap();
+        assert!(cache.cached_auction().0.orders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn notifies_about_new_auctions() {
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning(|_| {
+            Ok(DbOrders {
+                orders: Vec::new(),
+                latest_settlement_block: 0,
+            })
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|_| Vec::new());
+        let mut native = MockNativePriceEstimating::new();
+        native
+            .expect_estimate_native_prices()
+            .returning(|_| futures::stream::empty().boxed());
+
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
+
+        let mut stream = cache.auction_stream();
+        assert_eq!(stream.borrow().block, 0);
+
+        cache.update(42).await.unwrap();
+        stream.changed().await.unwrap();
+        assert_eq!(stream.borrow().block, 42);
+    }
+
+    #[tokio::test]
+    async fn shuts_down_update_task() {
+        let cache = CacheBuilder::default().build();
+
+        tokio::time::timeout(Duration::from_secs(1), cache.shutdown())
+            .await
+            .unwrap();
+        assert!(cache.update_task_handle.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn freshness_depends_on_update_time() {
+        let cache = CacheBuilder::default().build();
+
+        let max_age = Duration::from_secs(10);
+        assert!(cache.is_fresh(max_age));
+        cache.cache.lock().unwrap().orders.update_time = Instant::now() - max_age * 2;
+        assert!(!cache.is_fresh(max_age));
+        cache.shutdown().await;
+    }
+
+    #[test]
+    fn jitters_update_interval() {
+        let interval = Duration::from_secs(2);
+        assert_eq!(jittered_interval(interval, 0.), interval);
+        for _ in 0..100 {
+            let jittered = jittered_interval(interval, 0.2);
+            assert!(jittered >= Duration::from_millis(1600));
+            assert!(jittered <= Duration::from_millis(2400));
+        }
+    }
+
+    #[tokio::test]
+    async fn reloads_banned_users() {
+        let owner = H160([1; 20]);
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning(move |_| {
+            Ok(DbOrders {
+                orders: vec![Order {
+                    creation: OrderCreation {
+                        sell_token: H160([2; 20]),
+                        buy_token: H160([3; 20]),
+                        sell_amount: 1.into(),
+                        buy_amount: 1.into(),
+                        ..Default::default()
+                    },
+                    metadata: OrderMetadata {
+                        owner,
+                        ..Default::default()
+                    },
+                }],
+                latest_settlement_block: 0,
+            })
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+        let mut native = MockNativePriceEstimating::new();
+        native.expect_estimate_native_prices().returning(|tokens| {
+            futures::stream::iter(std::iter::repeat(Ok(1.0)).take(tokens.len()).enumerate()).boxed()
+        });
+
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
+
+        cache.update(0).await.unwrap();
+        assert_eq!(cache.cached_solvable_orders().orders.len(), 1);
+
+        cache.set_banned_users(hashset!(owner));
+        cache.update(0).await.unwrap();
+        assert!(cache.cached_solvable_orders().orders.is_empty());
+    }
+
+    #[test]
+    fn filters_banned_app_data() {
+        let banned = H256([1; 32]);
+        let orders = vec![
+            OrderBuilder::default().with_app_data(banned.0).build(),
+            OrderBuilder::default().with_app_data([2; 32]).build(),
+        ];
+
+        let filtered_orders = filter_banned_app_data_orders(orders.clone(), &hashset!(banned));
+        assert_eq!(filtered_orders, [orders[1].clone()]);
+    }
+
+    #[tokio::test]
+    async fn explains_why_orders_are_removed() {
+        let token = H160::from_low_u64_be;
+        let (bad_token, unpriced_token) = (token(0xbad), token(0xdead));
+        let (banned_user, poor_user) = (H160([0xba; 20]), H160([0x00; 20]));
+        let banned_app_data = H256([0xba; 32]);
+        let order =
+            |uid: u8, owner: H160, sell_token, buy_token, sell_amount: u64, buy_amount: u64| {
+                Order {
+                    creation: OrderCreation {
+                        sell_token,
+                        buy_token,
+                        sell_amount: sell_amount.into(),
+                        buy_amount: buy_amount.into(),
+                        ..Default::default()
+                    },
+                    metadata: OrderMetadata {
+                        uid: OrderUid([uid; 56]),
+                        owner,
+                        creation_date: DateTime::from_utc(
+                            NaiveDateTime::from_timestamp(uid.into(), 0),
+                            Utc,
+                        ),
+                        ..Default::default()
+                    },
+                }
+            };
+        let user = |i: u8| H160([i; 20]);
+        let mut banned_app_data_order = order(3, user(3), token(1), token(2), 100, 100);
+        banned_app_data_order.creation.app_data.0 = banned_app_data.0;
+        let orders = vec![
+            order(1, user(1), token(1), token(2), 100, 100),
+            order(2, banned_user, token(1), token(2), 100, 100),
+            banned_app_data_order,
+            order(4, user(4), token(1), token(1), 100, 100),
+            order(5, user(5), bad_token, token(2), 100, 100),
+            order(6, poor_user, token(1), token(2), 100, 100),
+            order(7, user(7), token(1), token(2), 100, 100),
+            order(8, user(7), token(1), token(2), 100, 100),
+            order(9, user(9), unpriced_token, token(2), 100, 100),
+            order(10, user(10), token(1), token(2), 1, 1),
+            order(11, user(11), token(1), token(2), 100, 200),
+            order(12, user(12), token(1), token(2), 0, 0),
+        ];
+
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning(move |_| {
+            Ok(DbOrders {
+                orders: orders.clone(),
+                latest_settlement_block: 0,
+            })
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(move |queries| {
+                queries
+                    .iter()
+                    .map(|query| {
+                        Ok(if query.owner == poor_user {
+                            U256::zero()
+                        } else {
+                            U256::MAX
+                        })
+                    })
+                    .collect()
+            });
+        let mut native = MockNativePriceEstimating::new();
+        native
+            .expect_estimate_native_prices()
+            .returning(move |tokens| {
+                let results = tokens
+                    .iter()
+                    .map(|token| {
+                        if *token == unpriced_token {
+                            Err(PriceEstimationError::NoLiquidity)
+                        } else {
+                            Ok(1.0)
+                        }
+                    })
+                    .enumerate()
+                    .collect::<Vec<_>>();
+                futures::stream::iter(results).boxed()
+            });
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            banned_users: hashset!(banned_user),
+            banned_app_data: hashset!(banned_app_data),
+            balance_fetcher: Arc::new(balance_fetcher),
+            bad_token_detector: Arc::new(
+                shared::bad_token::list_based::ListBasedDetector::deny_list(vec![bad_token]),
+            ),
+            native_price_estimator: Arc::new(native),
+            config: SolvableOrdersCacheConfig {
+                max_orders_per_user: Some(1),
+                order_prioritization: OrderPrioritization::CreationDate,
+                min_order_native_value: Some(10.into()),
+                max_limit_price_deviation_bps: Some(100),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .build();
+
+        for (uid, report) in [
+            (0, OrderFilterReport::Expired),
+            (1, OrderFilterReport::Solvable),
+            (2, OrderFilterReport::BannedUser),
+            (3, OrderFilterReport::BannedAppData),
+            (4, OrderFilterReport::SelfTrade),
+            (5, OrderFilterReport::UnsupportedToken),
+            (6, OrderFilterReport::InsufficientBalance),
+            (7, OrderFilterReport::UserOrderLimit),
+            (8, OrderFilterReport::Solvable),
+            (9, OrderFilterReport::MissingPrice),
+            (10, OrderFilterReport::Dust),
+            (11, OrderFilterReport::Unmarketable),
+            (12, OrderFilterReport::ZeroAmount),
+        ] {
+            let explained = cache.explain(&OrderUid([uid; 56])).await.unwrap();
+            assert_eq!(explained, report, "order {}", uid);
+        }
+        cache.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn trusted_tokens_bypass_bad_token_detector() {
+        let bad_token = H160([2; 20]);
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning(move |_| {
+            Ok(DbOrders {
+                orders: vec![Order {
+                    creation: OrderCreation {
+                        sell_token: bad_token,
+                        buy_token: H160([3; 20]),
+                        sell_amount: 1.into(),
+                        buy_amount: 1.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }],
+                latest_settlement_block: 0,
+            })
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+        let mut native = MockNativePriceEstimating::new();
+        native.expect_estimate_native_prices().returning(|tokens| {
+            futures::stream::iter(std::iter::repeat(Ok(1.0)).take(tokens.len()).enumerate()).boxed()
+        });
+
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            trusted_tokens: hashset!(bad_token),
+            balance_fetcher: Arc::new(balance_fetcher),
+            bad_token_detector: Arc::new(
+                shared::bad_token::list_based::ListBasedDetector::deny_list(vec![bad_token]),
+            ),
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
+
+        cache.update(0).await.unwrap();
+        assert_eq!(cache.cached_solvable_orders().orders.len(), 1);
+
+        cache.set_trusted_tokens(Default::default());
+        cache.update(0).await.unwrap();
+        assert!(cache.cached_solvable_orders().orders.is_empty());
+    }
+
     #[test]
     fn computes_u256_prices_normalized_to_1e18() {
         assert_eq!(
@@ -708,13 +2855,23 @@ mod tests {
                 }
             });
 
-        let (filtered_orders, prices) = get_orders_with_native_prices(
-            orders.clone(),
+        let native_prices = get_native_prices(
+            &traded_tokens(&orders),
             &native_price_estimator,
             Instant::now() + MAX_AUCTION_CREATION_TIME,
-            &NoopMetrics,
+            None,
+            None,
         )
         .await;
+        let (filtered_orders, prices, _) = get_orders_with_native_prices(
+            orders.clone(),
+            &native_prices,
+            &Default::default(),
+            NATIVE_TOKEN,
+            None,
+            None,
+            &NoopMetrics,
+        );
 
         assert_eq!(filtered_orders, [orders[2].clone()]);
         assert_eq!(
@@ -722,17 +2879,305 @@ mod tests {
             btreemap! {
                 token1 => U256::from(2_000_000_000_000_000_000_u128),
                 token3 => U256::from(250_000_000_000_000_000_u128),
+                NATIVE_TOKEN => native_token_price(),
             }
         );
     }
 
     #[test]
-    fn computes_max_transfer_out_amount_for_order() {
-        // For fill-or-kill orders, we don't overflow even for very large buy
-        // orders (where `{sell,fee}_amount * buy_amount` would overflow).
-        assert_eq!(
-            max_transfer_out_amount(&Order {
-                creation: OrderCreation {
+    fn includes_native_token_price() {
+        let token1 = H160::from_low_u64_be(1);
+        let token2 = H160::from_low_u64_be(2);
+        let orders = vec![OrderBuilder::default()
+            .with_sell_token(token1)
+            .with_buy_token(token2)
+            .build()];
+        let native_prices = NativePrices {
+            prices: hashmap! {
+                token1 => 1.into(),
+                token2 => 2.into(),
+            },
+            ..Default::default()
+        };
+
+        let (_, prices, _) = get_orders_with_native_prices(
+            orders.clone(),
+            &native_prices,
+            &Default::default(),
+            NATIVE_TOKEN,
+            None,
+            None,
+            &NoopMetrics,
+        );
+        assert_eq!(prices[&NATIVE_TOKEN], U256::exp10(18));
+
+        // The native token price is included even when no order can be priced.
+        let (filtered_orders, prices, _) = get_orders_with_native_prices(
+            orders,
+            &Default::default(),
+            &Default::default(),
+            NATIVE_TOKEN,
+            None,
+            None,
+            &NoopMetrics,
+        );
+        assert!(filtered_orders.is_empty());
+        assert_eq!(prices, btreemap! { NATIVE_TOKEN => U256::exp10(18) });
+    }
+
+    #[test]
+    fn native_token_price_respects_overrides() {
+        let token = H160::from_low_u64_be(1);
+        let native_prices = NativePrices {
+            prices: hashmap! { token => 1.into() },
+            ..Default::default()
+        };
+        let get_prices = |orders: Vec<Order>, price_overrides: HashMap<H160, U256>| {
+            get_orders_with_native_prices(
+                orders,
+                &native_prices,
+                &price_overrides,
+                NATIVE_TOKEN,
+                None,
+                None,
+                &NoopMetrics,
+            )
+            .1
+        };
+        let price_overrides = hashmap! { NATIVE_TOKEN => 5.into() };
+
+        // Without orders trading the native token.
+        assert_eq!(
+            get_prices(Vec::new(), price_overrides.clone())[&NATIVE_TOKEN],
+            5.into()
+        );
+
+        // With an order trading the native token.
+        let orders = vec![OrderBuilder::default()
+            .with_sell_token(token)
+            .with_buy_token(NATIVE_TOKEN)
+            .build()];
+        assert_eq!(get_prices(orders, price_overrides)[&NATIVE_TOKEN], 5.into());
+
+        // Static native prices end up with the estimated prices.
+        let native_prices = NativePrices {
+            prices: hashmap! { NATIVE_TOKEN => 7.into() },
+            ..Default::default()
+        };
+        let (_, prices, _) = get_orders_with_native_prices(
+            Vec::new(),
+            &native_prices,
+            &Default::default(),
+            NATIVE_TOKEN,
+            None,
+            None,
+            &NoopMetrics,
+        );
+        assert_eq!(prices[&NATIVE_TOKEN], 7.into());
+    }
+
+    #[test]
+    fn price_overrides_take_precedence_over_estimates() {
+        let token1 = H160::from_low_u64_be(1);
+        let token2 = H160::from_low_u64_be(2);
+        let token3 = H160::from_low_u64_be(3);
+        let orders = vec![
+            OrderBuilder::default()
+                .with_sell_token(token1)
+                .with_buy_token(token2)
+                .build(),
+            OrderBuilder::default()
+                .with_sell_token(token2)
+                .with_buy_token(token3)
+                .build(),
+        ];
+        let native_prices = NativePrices {
+            prices: hashmap! {
+                token1 => 1.into(),
+                token2 => 2.into(),
+            },
+            ..Default::default()
+        };
+        let price_overrides = hashmap! {
+            token2 => 5.into(),
+            token3 => 7.into(),
+        };
+
+        let (filtered_orders, prices, _) = get_orders_with_native_prices(
+            orders.clone(),
+            &native_prices,
+            &price_overrides,
+            NATIVE_TOKEN,
+            None,
+            None,
+            &NoopMetrics,
+        );
+
+        assert_eq!(filtered_orders, orders);
+        assert_eq!(
+            prices,
+            btreemap! {
+                token1 => U256::from(1),
+                token2 => U256::from(5),
+                token3 => U256::from(7),
+                NATIVE_TOKEN => native_token_price(),
+            }
+        );
+    }
+
+    #[test]
+    fn filters_orders_below_min_native_value() {
+        let token1 = H160::from_low_u64_be(1);
+        let token2 = H160::from_low_u64_be(2);
+        let order = |sell_amount: u64, buy_amount: u64| {
+            OrderBuilder::default()
+                .with_sell_token(token1)
+                .with_buy_token(token2)
+                .with_sell_amount(sell_amount.into())
+                .with_buy_amount(buy_amount.into())
+                .build()
+        };
+        let orders = vec![order(1, 1), order(10, 1), order(1, 5), order(100, 100)];
+        let native_prices = NativePrices {
+            prices: hashmap! {
+                token1 => U256::exp10(18),
+                token2 => U256::exp10(18) * 2,
+            },
+            ..Default::default()
+        };
+
+        let (filtered_orders, _, _) = get_orders_with_native_prices(
+            orders.clone(),
+            &native_prices,
+            &Default::default(),
+            NATIVE_TOKEN,
+            Some(10.into()),
+            None,
+            &NoopMetrics,
+        );
+
+        assert_eq!(
+            filtered_orders,
+            [orders[1].clone(), orders[2].clone(), orders[3].clone()]
+        );
+    }
+
+    #[test]
+    fn computes_auction_diff() {
+        let order = |uid: u8| Order {
+            metadata: OrderMetadata {
+                uid: OrderUid([uid; 56]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let previous = vec![order(0), order(1), order(2)];
+        let current = vec![order(1), order(2), order(3), order(4)];
+
+        assert_eq!(
+            auction_diff(&previous, &current),
+            AuctionDiff {
+                added: hashset! { OrderUid([3; 56]), OrderUid([4; 56]) },
+                removed: hashset! { OrderUid([0; 56]) },
+            }
+        );
+        assert_eq!(auction_diff(&current, &current), AuctionDiff::default());
+    }
+
+    #[test]
+    fn records_bounded_auction_history() {
+        let auction = |block: u64, latest_settlement_block: u64| Auction {
+            block,
+            latest_settlement_block,
+            orders: Default::default(),
+            prices: Default::default(),
+        };
+        let mut history = VecDeque::new();
+        record_auction(&mut history, auction(1, 0), 2);
+        record_auction(&mut history, auction(2, 0), 2);
+        record_auction(&mut history, auction(2, 1), 2);
+        assert_eq!(
+            history.iter().map(|a| a.block).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(history.back().unwrap().latest_settlement_block, 1);
+
+        record_auction(&mut history, auction(3, 0), 2);
+        assert_eq!(
+            history.iter().map(|a| a.block).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn filters_unmarketable_orders() {
+        let token1 = H160::from_low_u64_be(1);
+        let token2 = H160::from_low_u64_be(2);
+        let order = |sell_amount: u64, buy_amount: u64| {
+            OrderBuilder::default()
+                .with_sell_token(token1)
+                .with_buy_token(token2)
+                .with_sell_amount(sell_amount.into())
+                .with_buy_amount(buy_amount.into())
+                .build()
+        };
+        // At market prices 2 token1 buy 1 token2.
+        let orders = vec![
+            order(200, 100),
+            order(200, 101),
+            order(200, 102),
+            order(200, 50),
+        ];
+        let native_prices = NativePrices {
+            prices: hashmap! {
+                token1 => U256::exp10(18),
+                token2 => U256::exp10(18) * 2,
+            },
+            ..Default::default()
+        };
+
+        let (filtered_orders, _, _) = get_orders_with_native_prices(
+            orders.clone(),
+            &native_prices,
+            &Default::default(),
+            NATIVE_TOKEN,
+            None,
+            Some(100),
+            &NoopMetrics,
+        );
+
+        assert_eq!(
+            filtered_orders,
+            [orders[0].clone(), orders[1].clone(), orders[3].clone()]
+        );
+    }
+
+    #[test]
+    fn skips_recently_unpriceable_tokens() {
+        let token1 = H160::from_low_u64_be(1);
+        let token2 = H160::from_low_u64_be(2);
+        let token3 = H160::from_low_u64_be(3);
+        let now = Instant::now();
+        let ttl = Duration::from_secs(60);
+        let mut unpriceable_tokens = hashmap! {
+            token1 => now - Duration::from_secs(10),
+            token2 => now - Duration::from_secs(61),
+        };
+
+        let (tokens, skipped) =
+            skip_unpriceable_tokens(&[token1, token2, token3], &mut unpriceable_tokens, ttl, now);
+        assert_eq!(tokens, [token2, token3]);
+        assert_eq!(skipped, 1);
+        assert_eq!(unpriceable_tokens.keys().collect::<Vec<_>>(), [&token1]);
+    }
+
+    #[test]
+    fn computes_max_transfer_out_amount_for_order() {
+        // For fill-or-kill orders, we don't overflow even for very large buy
+        // orders (where `{sell,fee}_amount * buy_amount` would overflow).
+        assert_eq!(
+            max_transfer_out_amount(&Order {
+                creation: OrderCreation {
                     sell_amount: 1000.into(),
                     fee_amount: 337.into(),
                     buy_amount: U256::MAX,
@@ -746,56 +3191,1025 @@ mod tests {
             U256::from(1337),
         );
 
-        // Partially filled order scales amount.
-        assert_eq!(
-            max_transfer_out_amount(&Order {
-                creation: OrderCreation {
-                    sell_amount: 100.into(),
-                    buy_amount: 10.into(),
-                    fee_amount: 101.into(),
-                    kind: OrderKind::Buy,
-                    partially_fillable: true,
-                    ..Default::default()
-                },
-                metadata: OrderMetadata {
-                    executed_buy_amount: 9_u32.into(),
-                    ..Default::default()
-                },
+        // Partially filled order scales amount.
+        assert_eq!(
+            max_transfer_out_amount(&Order {
+                creation: OrderCreation {
+                    sell_amount: 100.into(),
+                    buy_amount: 10.into(),
+                    fee_amount: 101.into(),
+                    kind: OrderKind::Buy,
+                    partially_fillable: true,
+                    ..Default::default()
+                },
+                metadata: OrderMetadata {
+                    executed_buy_amount: 9_u32.into(),
+                    ..Default::default()
+                },
+            })
+            .unwrap(),
+            U256::from(20),
+        );
+    }
+
+    #[test]
+    fn max_transfer_out_amount_overflow() {
+        // For fill-or-kill orders, overflow if the total sell and fee amount
+        // overflows a uint. This kind of order cannot be filled by the
+        // settlement contract anyway.
+        assert!(max_transfer_out_amount(&Order {
+            creation: OrderCreation {
+                sell_amount: U256::MAX,
+                fee_amount: 1.into(),
+                partially_fillable: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .is_err());
+
+        // Handles overflow when computing fill ratio.
+        assert!(max_transfer_out_amount(&Order {
+            creation: OrderCreation {
+                sell_amount: 1000.into(),
+                fee_amount: 337.into(),
+                buy_amount: U256::MAX,
+                kind: OrderKind::Buy,
+                partially_fillable: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn skips_sell_orders_overflowing_max_transfer_out_amount() {
+        let order = |fee_amount: u64| Order {
+            creation: OrderCreation {
+                sell_amount: U256::MAX,
+                fee_amount: fee_amount.into(),
+                kind: OrderKind::Sell,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let balances = hashmap! {Query::from_order(&order(0)) => U256::MAX};
+        assert_eq!(
+            solvable_orders(vec![order(0)], &balances, Default::default(), &NoopMetrics),
+            [order(0)]
+        );
+        assert!(
+            solvable_orders(vec![order(1)], &balances, Default::default(), &NoopMetrics).is_empty()
+        );
+    }
+
+    #[test]
+    fn backoff_interval_doubles_up_to_max() {
+        let interval = Duration::from_secs(2);
+        let max = Duration::from_secs(10);
+        let backoff = |failures| backoff_interval(interval, max, failures).as_secs();
+        assert_eq!(backoff(0), 2);
+        assert_eq!(backoff(1), 4);
+        assert_eq!(backoff(2), 8);
+        assert_eq!(backoff(3), 10);
+        assert_eq!(backoff(100), 10);
+        assert_eq!(backoff_interval(interval, Duration::ZERO, 3), interval);
+    }
+
+    #[test]
+    fn order_dispositions_track_removal_reasons() {
+        let order = |uid: u8| Order {
+            metadata: OrderMetadata {
+                uid: OrderUid([uid; 56]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let orders = vec![order(1), order(2), order(3), order(4)];
+        let mut dispositions = OrderDispositions {
+            enabled: true,
+            remaining: orders.iter().map(|order| order.metadata.uid).collect(),
+            removed: Vec::new(),
+        };
+        dispositions.record("banned_user", &orders[1..]);
+        dispositions.record("self_trade", &orders[1..]);
+        dispositions.record_removed(&[
+            (OrderUid([2; 56]), "dust"),
+            (OrderUid([3; 56]), "unmarketable"),
+        ]);
+        assert_eq!(dispositions.remaining, hashset! {OrderUid([4; 56])});
+        dispositions.record("evicted", &orders[4..]);
+        assert!(dispositions.remaining.is_empty());
+        assert_eq!(
+            dispositions.removed,
+            [
+                (OrderUid([1; 56]), "banned_user"),
+                (OrderUid([2; 56]), "dust"),
+                (OrderUid([3; 56]), "unmarketable"),
+                (OrderUid([4; 56]), "evicted"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_secondary_native_price_estimator() {
+        let (token1, token2) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+        let mut primary = MockNativePriceEstimating::new();
+        primary
+            .expect_estimate_native_prices()
+            .returning(move |tokens| {
+                let results = tokens
+                    .iter()
+                    .map(|token| {
+                        if *token == token1 {
+                            Ok(1.0)
+                        } else {
+                            Err(PriceEstimationError::NoLiquidity)
+                        }
+                    })
+                    .enumerate()
+                    .collect::<Vec<_>>();
+                futures::stream::iter(results).boxed()
+            });
+        let mut secondary = MockNativePriceEstimating::new();
+        secondary
+            .expect_estimate_native_prices()
+            .withf(move |tokens| *tokens == [token2])
+            .returning(|_| futures::stream::iter([(0, Ok(2.0))]).boxed());
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut native_prices =
+            get_native_prices(&[token1, token2], &primary, deadline, None, None).await;
+        assert_eq!(native_prices.unpriceable_tokens, [token2]);
+        add_fallback_native_prices(
+            &[token1, token2],
+            &mut native_prices,
+            &secondary,
+            deadline,
+            None,
+        )
+        .await;
+
+        assert_eq!(
+            native_prices.prices,
+            hashmap! {
+                token1 => to_normalized_price(1.0).unwrap(),
+                token2 => to_normalized_price(2.0).unwrap(),
+            }
+        );
+        assert!(native_prices.unpriceable_tokens.is_empty());
+        assert_eq!(native_prices.fallback_prices, 1);
+    }
+
+    #[test]
+    fn filters_new_orders() {
+        let now = Utc::now();
+        let order = |age: i64| Order {
+            metadata: OrderMetadata {
+                creation_date: now - chrono::Duration::seconds(age),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (fresh, aged) = (order(5), order(60));
+        assert_eq!(
+            filter_new_orders(vec![fresh, aged.clone()], Duration::from_secs(30), now),
+            [aged]
+        );
+    }
+
+    #[test]
+    fn parses_order_prioritization() {
+        use clap::ArgEnum as _;
+        assert_eq!(
+            OrderPrioritization::from_str("creation-date", true).unwrap(),
+            OrderPrioritization::CreationDate
+        );
+        assert_eq!(
+            OrderPrioritization::from_str("Limit-Price", true).unwrap(),
+            OrderPrioritization::LimitPrice
+        );
+        assert!(OrderPrioritization::from_str("surplus", true).is_err());
+    }
+
+    #[test]
+    fn trims_oversized_auction() {
+        let native_token = H160::from_low_u64_be(0);
+        let order = |token: u64, sell_amount: u64| {
+            OrderBuilder::default()
+                .with_sell_token(H160::from_low_u64_be(token))
+                .with_buy_token(native_token)
+                .with_sell_amount(sell_amount.into())
+                .with_buy_amount(1.into())
+                .build()
+        };
+        let orders = (1..=10).map(|i| order(i, i * 100)).collect::<Vec<_>>();
+        let mut prices = (1..=10)
+            .map(|i| (H160::from_low_u64_be(i), U256::exp10(18)))
+            .collect::<BTreeMap<_, _>>();
+        prices.insert(native_token, U256::exp10(18));
+        let mut auction = Auction {
+            block: 1,
+            latest_settlement_block: 0,
+            orders,
+            prices,
+        };
+        assert_eq!(
+            trim_auction(&mut auction.clone(), usize::MAX, native_token),
+            0
+        );
+
+        let max_size = json_size(&auction) / 2;
+        let trimmed = trim_auction(&mut auction, max_size, native_token);
+        assert!(trimmed > 0);
+        assert!(json_size(&auction) <= max_size);
+        assert_eq!(auction.orders.len(), 10 - trimmed);
+        // The most valuable orders are kept.
+        assert!(auction
+            .orders
+            .iter()
+            .all(|order| order.creation.sell_amount > U256::from(trimmed as u64 * 100)));
+        assert_eq!(auction.prices.len(), auction.orders.len() + 1);
+        assert!(auction.prices.contains_key(&native_token));
+    }
+
+    #[tokio::test]
+    async fn snapshot_contains_cache_state() {
+        let order = OrderBuilder::default()
+            .with_sell_token(H160::from_low_u64_be(1))
+            .with_buy_token(H160::from_low_u64_be(2))
+            .with_sell_amount(1.into())
+            .with_buy_amount(1.into())
+            .build();
+        let order_ = order.clone();
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning(move |_| {
+            Ok(DbOrders {
+                orders: vec![order_.clone()],
+                latest_settlement_block: 0,
+            })
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+        let mut native = MockNativePriceEstimating::new();
+        native.expect_estimate_native_prices().returning(|tokens| {
+            let results = tokens
+                .iter()
+                .map(|_| Ok(1.0))
+                .enumerate()
+                .collect::<Vec<_>>();
+            futures::stream::iter(results).boxed()
+        });
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
+        cache.update(0).await.unwrap();
+
+        let snapshot = cache.snapshot();
+        assert_eq!(snapshot.orders, [order.clone()]);
+        assert_eq!(snapshot.balances, [(Query::from_order(&order), U256::MAX)]);
+        assert_eq!(snapshot.auction, cache.cached_auction().0);
+        assert!(serde_json::to_value(&snapshot).is_ok());
+        cache.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn evicted_order_leaves_auction() {
+        let order = |uid: u32| {
+            let mut order = OrderBuilder::default()
+                .with_sell_token(H160::from_low_u64_be(1))
+                .with_buy_token(H160::from_low_u64_be(2))
+                .with_sell_amount(1.into())
+                .with_buy_amount(1.into())
+                .build();
+            order.metadata.uid = OrderUid::from_integer(uid);
+            order
+        };
+        let orders = vec![order(1), order(2)];
+        // The orders in the database and the order that gets cancelled during the next update.
+        let db_orders = Arc::new(Mutex::new(orders.clone()));
+        let cancel_during_update = Arc::new(Mutex::new(None::<OrderUid>));
+        let cache_slot = Arc::new(Mutex::new(Weak::<SolvableOrdersCache>::new()));
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning({
+            let (db_orders, cancel_during_update, cache_slot) = (
+                db_orders.clone(),
+                cancel_during_update.clone(),
+                cache_slot.clone(),
+            );
+            move |_| {
+                let orders = db_orders.lock().unwrap().clone();
+                if let Some(uid) = cancel_during_update.lock().unwrap().take() {
+                    db_orders
+                        .lock()
+                        .unwrap()
+                        .retain(|order| order.metadata.uid != uid);
+                    if let Some(cache) = cache_slot.lock().unwrap().upgrade() {
+                        cache.evict_order(&uid);
+                    }
+                }
+                Ok(DbOrders {
+                    orders,
+                    latest_settlement_block: 0,
+                })
+            }
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+        let mut native = MockNativePriceEstimating::new();
+        native.expect_estimate_native_prices().returning(|tokens| {
+            let results = tokens
+                .iter()
+                .map(|_| Ok(1.0))
+                .enumerate()
+                .collect::<Vec<_>>();
+            futures::stream::iter(results).boxed()
+        });
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
+        *cache_slot.lock().unwrap() = Arc::downgrade(&cache);
+        cache.update(0).await.unwrap();
+        assert_eq!(cache.cached_auction().0.orders.len(), 2);
+
+        // Cancel the first order the way `Orderbook::cancel_order` does.
+        let evicted = orders[0].metadata.uid;
+        db_orders
+            .lock()
+            .unwrap()
+            .retain(|order| order.metadata.uid != evicted);
+        cache.evict_order(&evicted);
+        let uids = |orders: &[Order]| {
+            orders
+                .iter()
+                .map(|order| order.metadata.uid)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            uids(&cache.cached_auction().0.orders),
+            [orders[1].metadata.uid]
+        );
+        assert_eq!(
+            uids(&cache.cached_solvable_orders().orders),
+            [orders[1].metadata.uid]
+        );
+        assert_eq!(
+            uids(&cache.auction_stream().borrow().orders),
+            [orders[1].metadata.uid]
+        );
+        assert_eq!(
+            uids(&cache.auction_at_block(0).unwrap().orders),
+            [orders[1].metadata.uid]
+        );
+
+        // An update that fetched an order from the database before it got cancelled doesn't add it
+        // back to the auction.
+        *cancel_during_update.lock().unwrap() = Some(orders[1].metadata.uid);
+        cache.update(1).await.unwrap();
+        assert!(cache.cached_auction().0.orders.is_empty());
+        assert!(cache.auction_stream().borrow().orders.is_empty());
+        assert!(cache.auction_at_block(1).unwrap().orders.is_empty());
+        cache.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn static_native_prices_let_orders_through() {
+        let (sell_token, buy_token) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+        let order = OrderBuilder::default()
+            .with_sell_token(sell_token)
+            .with_buy_token(buy_token)
+            .with_sell_amount(1.into())
+            .with_buy_amount(1.into())
+            .build();
+        let order_ = order.clone();
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning(move |_| {
+            Ok(DbOrders {
+                orders: vec![order_.clone()],
+                latest_settlement_block: 0,
+            })
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+        // No token can be estimated.
+        let mut native = MockNativePriceEstimating::new();
+        native.expect_estimate_native_prices().returning(|tokens| {
+            let results = tokens
+                .iter()
+                .map(|_| Err(PriceEstimationError::NoLiquidity))
+                .enumerate()
+                .collect::<Vec<_>>();
+            futures::stream::iter(results).boxed()
+        });
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            native_price_estimator: Arc::new(native),
+            config: SolvableOrdersCacheConfig {
+                static_native_prices: hashmap! {
+                    sell_token => U256::exp10(18),
+                    buy_token => U256::exp10(17),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .build();
+        cache.update(0).await.unwrap();
+
+        let auction = cache.cached_auction().0;
+        assert_eq!(auction.orders.len(), 1);
+        assert_eq!(auction.orders[0].metadata.uid, order.metadata.uid);
+        assert_eq!(auction.prices.get(&sell_token), Some(&U256::exp10(18)));
+        assert_eq!(auction.prices.get(&buy_token), Some(&U256::exp10(17)));
+        cache.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn update_errors_identify_failing_dependency() {
+        let order = OrderBuilder::default()
+            .with_sell_token(H160::from_low_u64_be(1))
+            .with_buy_token(H160::from_low_u64_be(2))
+            .with_sell_amount(1.into())
+            .with_buy_amount(1.into())
+            .build();
+        let orders = move || {
+            let order = order.clone();
+            let mut order_storing = MockOrderStoring::new();
+            order_storing.expect_solvable_orders().returning(move |_| {
+                Ok(DbOrders {
+                    orders: vec![order.clone()],
+                    latest_settlement_block: 0,
+                })
+            });
+            order_storing
+        };
+        let balances = |ok: bool| {
+            let mut balance_fetcher = MockBalanceFetching::new();
+            balance_fetcher
+                .expect_get_balances()
+                .returning(move |queries| {
+                    queries
+                        .iter()
+                        .map(|_| {
+                            if ok {
+                                Ok(U256::MAX)
+                            } else {
+                                Err(anyhow::anyhow!("node down"))
+                            }
+                        })
+                        .collect()
+                });
+            balance_fetcher
+        };
+        let bad_tokens = |ok: bool| {
+            let mut bad_token_detector = shared::bad_token::MockBadTokenDetecting::new();
+            bad_token_detector.expect_detect().returning(move |_| {
+                if ok {
+                    Ok(shared::bad_token::TokenQuality::Good)
+                } else {
+                    Err(anyhow::anyhow!("detector down"))
+                }
+            });
+            bad_token_detector
+        };
+        let native_prices = || {
+            let mut native = MockNativePriceEstimating::new();
+            native
+                .expect_estimate_native_prices()
+                .returning(|_| futures::stream::pending().boxed());
+            native
+        };
+        async fn update(
+            order_storing: MockOrderStoring,
+            balance_fetcher: MockBalanceFetching,
+            bad_token_detector: shared::bad_token::MockBadTokenDetecting,
+            native: MockNativePriceEstimating,
+        ) -> Result<(), UpdateError> {
+            let cache = CacheBuilder {
+                database: Arc::new(order_storing),
+                balance_fetcher: Arc::new(balance_fetcher),
+                bad_token_detector: Arc::new(bad_token_detector),
+                native_price_estimator: Arc::new(native),
+                ..Default::default()
+            }
+            .build();
+            let result = cache.update(0).await;
+            cache.shutdown().await;
+            result
+        }
+
+        let mut failing_database = MockOrderStoring::new();
+        failing_database
+            .expect_solvable_orders()
+            .returning(|_| Err(anyhow::anyhow!("database down")));
+        assert!(matches!(
+            update(
+                failing_database,
+                balances(true),
+                bad_tokens(true),
+                native_prices()
+            )
+            .await,
+            Err(UpdateError::DatabaseError(_))
+        ));
+        assert!(matches!(
+            update(orders(), balances(true), bad_tokens(false), native_prices()).await,
+            Err(UpdateError::BadTokenDetection(_))
+        ));
+        assert!(matches!(
+            update(orders(), balances(false), bad_tokens(true), native_prices()).await,
+            Err(UpdateError::BalanceFetchError(_))
+        ));
+        // Without a previous auction to keep an estimator timeout still publishes an auction.
+        assert!(
+            update(orders(), balances(true), bad_tokens(true), native_prices())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn price_overrides_rescue_timed_out_price_estimator() {
+        let (sell_token, buy_token) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+        let order = OrderBuilder::default()
+            .with_sell_token(sell_token)
+            .with_buy_token(buy_token)
+            .with_sell_amount(1.into())
+            .with_buy_amount(1.into())
+            .build();
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning({
+            let order = order.clone();
+            move |_| {
+                Ok(DbOrders {
+                    orders: vec![order.clone()],
+                    latest_settlement_block: 0,
+                })
+            }
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+        let mut native = MockNativePriceEstimating::new();
+        native
+            .expect_estimate_native_prices()
+            .times(1)
+            .returning(|tokens| {
+                futures::stream::iter(std::iter::repeat(Ok(1.0)).take(tokens.len()).enumerate())
+                    .boxed()
+            });
+        native
+            .expect_estimate_native_prices()
+            .returning(|_| futures::stream::pending().boxed());
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
+
+        cache.update(0).await.unwrap();
+        let auction = cache.cached_auction().0;
+        assert_eq!(auction.orders.len(), 1);
+
+        // The estimator times out so the previous auction is kept.
+        assert!(matches!(
+            cache.update(1).await,
+            Err(UpdateError::PriceEstimationTimeout)
+        ));
+        assert_eq!(cache.cached_auction().0, auction);
+
+        // Overrides for the traded tokens let the order through although the estimator still
+        // times out.
+        cache.set_price_override(sell_token, 5.into());
+        cache.set_price_override(buy_token, 7.into());
+        cache.update(2).await.unwrap();
+        let auction = cache.cached_auction().0;
+        assert_eq!(auction.block, 2);
+        assert_eq!(auction.orders.len(), 1);
+        assert_eq!(auction.prices.get(&sell_token), Some(&5.into()));
+        assert_eq!(auction.prices.get(&buy_token), Some(&7.into()));
+        cache.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn remembers_unpriced_tokens() {
+        let (token1, token2) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+        let order = OrderBuilder::default()
+            .with_sell_token(token1)
+            .with_buy_token(token2)
+            .with_sell_amount(1.into())
+            .with_buy_amount(1.into())
+            .build();
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning(move |_| {
+            Ok(DbOrders {
+                orders: vec![order.clone()],
+                latest_settlement_block: 0,
             })
-            .unwrap(),
-            U256::from(20),
-        );
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+        let mut native = MockNativePriceEstimating::new();
+        native
+            .expect_estimate_native_prices()
+            .returning(move |tokens| {
+                let results = tokens
+                    .iter()
+                    .map(|token| {
+                        if *token == token2 {
+                            Err(PriceEstimationError::NoLiquidity)
+                        } else {
+                            Ok(1.0)
+                        }
+                    })
+                    .enumerate()
+                    .collect::<Vec<_>>();
+                futures::stream::iter(results).boxed()
+            });
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
+
+        assert!(cache.last_unpriced_tokens().is_empty());
+        cache.update(0).await.unwrap();
+        assert_eq!(cache.last_unpriced_tokens(), [token2]);
+        assert!(cache.cached_solvable_orders().orders.is_empty());
+        cache.shutdown().await;
     }
 
-    #[test]
-    fn max_transfer_out_amount_overflow() {
-        // For fill-or-kill orders, overflow if the total sell and fee amount
-        // overflows a uint. This kind of order cannot be filled by the
-        // settlement contract anyway.
-        assert!(max_transfer_out_amount(&Order {
-            creation: OrderCreation {
-                sell_amount: U256::MAX,
-                fee_amount: 1.into(),
-                partially_fillable: false,
+    #[tokio::test]
+    async fn prewarm_populates_balances() {
+        let order = OrderBuilder::default()
+            .with_sell_token(H160::from_low_u64_be(1))
+            .with_buy_token(H160::from_low_u64_be(2))
+            .with_sell_amount(1.into())
+            .with_buy_amount(1.into())
+            .build();
+        let query = Query::from_order(&order);
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning({
+            let order = order.clone();
+            move |_| {
+                Ok(DbOrders {
+                    orders: vec![order.clone()],
+                    latest_settlement_block: 0,
+                })
+            }
+        });
+        // The update in the same block reuses the prewarmed balance.
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .times(1)
+            .returning(|queries| queries.iter().map(|_| Ok(5.into())).collect());
+        let mut native = MockNativePriceEstimating::new();
+        native.expect_estimate_native_prices().returning(|tokens| {
+            let results = (0..tokens.len()).map(|i| (i, Ok(1.0))).collect::<Vec<_>>();
+            futures::stream::iter(results).boxed()
+        });
+        let (_, receiver) = tokio::sync::watch::channel(shared::current_block::Block {
+            number: Some(1.into()),
+            ..Default::default()
+        });
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            current_block: receiver,
+            native_price_estimator: Arc::new(native),
+            ..Default::default()
+        }
+        .build();
+
+        assert_eq!(cache.cached_balance(&query), None);
+        cache.prewarm().await.unwrap();
+        assert_eq!(cache.cached_balance(&query), Some(5.into()));
+        assert!(cache.cached_solvable_orders().orders.is_empty());
+
+        cache.update(1).await.unwrap();
+        assert_eq!(cache.cached_solvable_orders().orders.len(), 1);
+        cache.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limits_concurrent_native_price_estimates() {
+        let tokens = (1..=5).map(H160::from_low_u64_be).collect::<Vec<_>>();
+        let in_flight = Arc::new(AtomicU64::new(0));
+        let max_in_flight = Arc::new(AtomicU64::new(0));
+        let mut native_price_estimator = MockNativePriceEstimating::new();
+        native_price_estimator
+            .expect_estimate_native_prices()
+            .times(5)
+            .returning({
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+                move |tokens| {
+                    assert_eq!(tokens.len(), 1);
+                    let in_flight = in_flight.clone();
+                    let max_in_flight = max_in_flight.clone();
+                    futures::stream::once(async move {
+                        let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_in_flight.fetch_max(current, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        (0, Ok(1.0))
+                    })
+                    .boxed()
+                }
+            });
+
+        let native_prices = get_native_prices(
+            &tokens,
+            &native_price_estimator,
+            Instant::now() + MAX_AUCTION_CREATION_TIME,
+            None,
+            Some(2),
+        )
+        .await;
+        assert!(!native_prices.timeout);
+        assert_eq!(native_prices.prices.len(), tokens.len());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn request_update_and_wait_includes_new_orders() {
+        let orders = Arc::new(Mutex::new(Vec::new()));
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning({
+            let orders = orders.clone();
+            move |_| {
+                Ok(DbOrders {
+                    orders: orders.lock().unwrap().clone(),
+                    latest_settlement_block: 0,
+                })
+            }
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+        let mut native = MockNativePriceEstimating::new();
+        native.expect_estimate_native_prices().returning(|tokens| {
+            let results = (0..tokens.len()).map(|i| (i, Ok(1.0))).collect::<Vec<_>>();
+            futures::stream::iter(results).boxed()
+        });
+        let (_, receiver) = tokio::sync::watch::channel(shared::current_block::Block {
+            number: Some(1.into()),
+            ..Default::default()
+        });
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            current_block: receiver,
+            native_price_estimator: Arc::new(native),
+            config: SolvableOrdersCacheConfig {
+                update_interval_jitter: 0.,
                 ..Default::default()
             },
             ..Default::default()
-        })
-        .is_err());
+        }
+        .build();
 
-        // Handles overflow when computing fill ratio.
-        assert!(max_transfer_out_amount(&Order {
-            creation: OrderCreation {
-                sell_amount: 1000.into(),
-                fee_amount: 337.into(),
-                buy_amount: U256::MAX,
-                kind: OrderKind::Buy,
-                partially_fillable: true,
+        let order = OrderBuilder::default()
+            .with_sell_token(H160::from_low_u64_be(1))
+            .with_buy_token(H160::from_low_u64_be(2))
+            .with_sell_amount(1.into())
+            .with_buy_amount(1.into())
+            .build();
+        orders.lock().unwrap().push(order.clone());
+        // Returns before the regular update interval elapses.
+        cache
+            .request_update_and_wait(Duration::from_secs(1))
+            .await
+            .unwrap();
+        let cached_orders = cache.cached_solvable_orders().orders;
+        assert_eq!(cached_orders.len(), 1);
+        assert_eq!(cached_orders[0].metadata.uid, order.metadata.uid);
+        cache.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn request_update_and_wait_times_out_when_updates_fail() {
+        let mut order_storing = MockOrderStoring::new();
+        order_storing
+            .expect_solvable_orders()
+            .returning(|_| Err(anyhow::anyhow!("database down")));
+        let (_, receiver) = tokio::sync::watch::channel(shared::current_block::Block {
+            number: Some(1.into()),
+            ..Default::default()
+        });
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            current_block: receiver,
+            config: SolvableOrdersCacheConfig {
+                update_interval_jitter: 0.,
                 ..Default::default()
             },
             ..Default::default()
-        })
-        .is_err());
+        }
+        .build();
+
+        assert!(cache
+            .request_update_and_wait(Duration::from_secs(5))
+            .await
+            .is_err());
+        cache.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn update_task_backs_off_on_failures() {
+        // The first three updates fail and all later ones succeed.
+        let update_times = Arc::new(Mutex::new(Vec::new()));
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning({
+            let update_times = update_times.clone();
+            move |_| {
+                let mut update_times = update_times.lock().unwrap();
+                update_times.push(Instant::now());
+                if update_times.len() <= 3 {
+                    Err(anyhow::anyhow!("database down"))
+                } else {
+                    Ok(DbOrders {
+                        orders: Vec::new(),
+                        latest_settlement_block: 0,
+                    })
+                }
+            }
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|_| Vec::new());
+        let mut native = MockNativePriceEstimating::new();
+        native
+            .expect_estimate_native_prices()
+            .returning(|_| futures::stream::empty().boxed());
+        let (_, receiver) = tokio::sync::watch::channel(shared::current_block::Block {
+            number: Some(1.into()),
+            ..Default::default()
+        });
+
+        let start = Instant::now();
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            current_block: receiver,
+            native_price_estimator: Arc::new(native),
+            config: SolvableOrdersCacheConfig {
+                update_interval_jitter: 0.,
+                max_update_backoff: Duration::from_secs(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .build();
+        tokio::time::sleep(Duration::from_secs(27)).await;
+        cache.shutdown().await;
+
+        let update_times = update_times
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|time| time.duration_since(start).as_secs())
+            .collect::<Vec<_>>();
+        // Waits 2s, 4s and 8s after each failure, capped at 10s, and resets to 2s after the first
+        // success.
+        assert_eq!(update_times, [2, 6, 14, 24, 26]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn update_task_survives_panicking_update() {
+        let order = OrderBuilder::default()
+            .with_sell_token(H160::from_low_u64_be(1))
+            .with_buy_token(H160::from_low_u64_be(2))
+            .with_sell_amount(1.into())
+            .with_buy_amount(1.into())
+            .build();
+        let updates = Arc::new(AtomicU64::new(0));
+        let mut order_storing = MockOrderStoring::new();
+        order_storing.expect_solvable_orders().returning({
+            let updates = updates.clone();
+            move |_| {
+                updates.fetch_add(1, Ordering::SeqCst);
+                Ok(DbOrders {
+                    orders: vec![order.clone()],
+                    latest_settlement_block: 0,
+                })
+            }
+        });
+        let mut balance_fetcher = MockBalanceFetching::new();
+        balance_fetcher
+            .expect_get_balances()
+            .returning(|queries| queries.iter().map(|_| Ok(U256::MAX)).collect());
+        let mut native = MockNativePriceEstimating::new();
+        native.expect_estimate_native_prices().returning(|tokens| {
+            let results = tokens
+                .iter()
+                .map(|_| Ok(1.0))
+                .enumerate()
+                .collect::<Vec<_>>();
+            futures::stream::iter(results).boxed()
+        });
+        // Publishing the first auction panics while the cache is locked which poisons the lock.
+        #[derive(Default)]
+        struct PanickingMetrics {
+            published: AtomicBool,
+            panics: AtomicU64,
+        }
+        impl AuctionMetrics for PanickingMetrics {
+            fn auction_updated(&self, _: u64, _: u64, _: u64, _: bool) {}
+            fn auction_update_stale(&self) {}
+            fn duplicate_orders_removed(&self, _: u64) {}
+            fn native_price_overlap(&self, _: u64, _: u64) {}
+            fn self_trade_orders_removed(&self, _: u64) {}
+            fn native_price_estimates_skipped(&self, _: u64) {}
+            fn orders_removed(&self, _: &'static str, _: u64) {}
+            fn auction_orders_changed(&self, _: u64, _: u64) {
+                assert!(
+                    self.published.swap(true, Ordering::SeqCst),
+                    "publishing the first auction panicked"
+                );
+            }
+            fn auction_update_duration(&self, _: Duration) {}
+            fn auction_update_failed(&self) {}
+            fn max_transfer_out_amount_failed(&self, _: OrderKind) {}
+            fn balances_cached(&self, _: u64) {}
+            fn auction_order_types(&self, _: u64, _: u64) {}
+            fn native_price_estimation_slow(&self) {}
+            fn native_prices_from_fallback(&self, _: u64) {}
+            fn auction_update_panicked(&self) {
+                self.panics.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        let metrics = Arc::new(PanickingMetrics::default());
+        let (_, receiver) = tokio::sync::watch::channel(shared::current_block::Block {
+            number: Some(1.into()),
+            ..Default::default()
+        });
+
+        let start = Instant::now();
+        let cache = CacheBuilder {
+            database: Arc::new(order_storing),
+            balance_fetcher: Arc::new(balance_fetcher),
+            current_block: receiver,
+            native_price_estimator: Arc::new(native),
+            metrics: metrics.clone(),
+            config: SolvableOrdersCacheConfig {
+                update_interval_jitter: 0.,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .build();
+        tokio::time::sleep(Duration::from_secs(9)).await;
+        let last_tick = cache.last_update_task_tick();
+        let auction = cache.cached_auction().0;
+        cache.shutdown().await;
+
+        // Updates at 2s (panics), 6s after the backoff and 8s. The later updates succeed despite
+        // the poisoned lock.
+        assert_eq!(updates.load(Ordering::SeqCst), 3);
+        assert_eq!(metrics.panics.load(Ordering::SeqCst), 1);
+        assert_eq!(last_tick.duration_since(start), Duration::from_secs(8));
+        assert_eq!(auction.orders.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn banned_users_survive_poisoned_lock() {
+        let cache = CacheBuilder::default().build();
+        let user = H160::from_low_u64_be(1);
+        let _ = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = cache.banned_users.write().unwrap();
+            panic!("poison the lock");
+        }));
+        assert!(cache.banned_users.is_poisoned());
+
+        cache.set_banned_users(hashset!(user));
+        assert_eq!(*read(&cache.banned_users), hashset!(user));
     }
 
     #[tokio::test(start_paused = true)]
@@ -830,22 +4244,69 @@ mod tests {
         ];
         // last token price won't be available
         let deadline = Instant::now() + Duration::from_secs_f32(3.5);
-        let (orders_, prices) = get_orders_with_native_prices(
-            orders.clone(),
+        let native_prices = get_native_prices(
+            &traded_tokens(&orders),
             &native_price_estimator,
             deadline,
-            &NoopMetrics,
+            None,
+            None,
         )
         .await;
+        assert!(native_prices.timeout);
+        let (orders_, prices, _) = get_orders_with_native_prices(
+            orders.clone(),
+            &native_prices,
+            &Default::default(),
+            NATIVE_TOKEN,
+            None,
+            None,
+            &NoopMetrics,
+        );
         assert_eq!(orders_.len(), 1);
         // It is not guaranteed which order is the included one because the function uses a hashset
         // for the tokens.
         assert!(orders_[0] == orders[0] || orders_[0] == orders[1]);
-        assert_eq!(prices.len(), 2);
+        assert_eq!(prices.len(), 3);
         assert!(prices.contains_key(&orders_[0].creation.sell_token));
         assert!(prices.contains_key(&orders_[0].creation.buy_token));
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn native_prices_report_soft_timeout() {
+        let mut native_price_estimator = MockNativePriceEstimating::new();
+        native_price_estimator
+            .expect_estimate_native_prices()
+            .returning(|tokens| {
+                let results = (0..tokens.len()).map(|i| (i, Ok(1.0))).collect::<Vec<_>>();
+                futures::stream::iter(results)
+                    .then(|price| async {
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        price
+                    })
+                    .boxed()
+            });
+        let tokens = (0..3).map(H160::from_low_u64_be).collect::<Vec<_>>();
+        let native_prices = |soft_deadline: Duration| {
+            let now = Instant::now();
+            get_native_prices(
+                &tokens,
+                &native_price_estimator,
+                now + Duration::from_secs(10),
+                Some(now + soft_deadline),
+                None,
+            )
+        };
+
+        let slow = native_prices(Duration::from_secs(2)).await;
+        assert!(slow.soft_timeout);
+        assert!(!slow.timeout);
+        assert_eq!(slow.prices.len(), 3);
+
+        let fast = native_prices(Duration::from_secs(5)).await;
+        assert!(!fast.soft_timeout);
+        assert!(!fast.timeout);
+    }
+
     #[test]
     fn filters_banned_users() {
         let banned_users = hashset!(H160([0xba; 20]), H160([0xbb; 20]));
@@ -883,6 +4344,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn filters_banned_receivers() {
+        let banned_users = hashset!(H160([0xba; 20]));
+        let order = |receiver| Order {
+            metadata: OrderMetadata {
+                owner: H160([1; 20]),
+                ..Default::default()
+            },
+            creation: OrderCreation {
+                receiver,
+                ..Default::default()
+            },
+        };
+        let orders = vec![
+            order(None),
+            order(Some(H160([0xba; 20]))),
+            order(Some(H160([2; 20]))),
+        ];
+
+        let filtered_orders = filter_banned_user_orders(orders.clone(), &banned_users);
+        assert_eq!(filtered_orders, [orders[0].clone(), orders[2].clone()]);
+    }
+
+    #[test]
+    fn filters_duplicate_orders() {
+        let order = |uid: u8, owner: u8| Order {
+            metadata: OrderMetadata {
+                uid: OrderUid([uid; 56]),
+                owner: H160([owner; 20]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let orders = vec![order(1, 1), order(1, 2), order(2, 3)];
+
+        let filtered_orders = filter_duplicate_orders(orders);
+        let filtered_owners = filtered_orders
+            .iter()
+            .map(|order| order.metadata.owner)
+            .collect::<Vec<_>>();
+        assert_eq!(filtered_owners, [H160([1; 20]), H160([3; 20])]);
+    }
+
+    #[test]
+    fn filters_self_trade_orders() {
+        let orders = vec![
+            OrderBuilder::default()
+                .with_sell_token(H160([1; 20]))
+                .with_buy_token(H160([1; 20]))
+                .build(),
+            OrderBuilder::default()
+                .with_sell_token(H160([1; 20]))
+                .with_buy_token(H160([2; 20]))
+                .build(),
+        ];
+
+        let filtered_orders = filter_self_trade_orders(orders.clone());
+        assert_eq!(filtered_orders, [orders[1].clone()]);
+    }
+
     #[test]
     fn filters_zero_amount_orders() {
         let orders = vec![
@@ -925,7 +4446,8 @@ mod tests {
 
         let balances = hashmap! {Query::from_order(&orders[0]) => U256::MAX};
         let expected_result = vec![orders[0].clone(), orders[1].clone()];
-        let mut filtered_orders = solvable_orders(orders, &balances);
+        let mut filtered_orders =
+            solvable_orders(orders, &balances, Default::default(), &NoopMetrics);
         // Deal with `solvable_orders()` sorting the orders.
         filtered_orders.sort_by_key(|order| order.metadata.creation_date);
         assert_eq!(expected_result, filtered_orders);
@@ -3,6 +3,7 @@
 //! For more information how the SOR solver works, check out
 //! https://dev.balancer.fi/resources/smart-order-router
 
+use crate::http_retry::HttpRetryPolicy;
 use anyhow::{ensure, Result};
 use ethcontract::{H160, H256, U256};
 use model::order::OrderKind;
@@ -24,6 +25,7 @@ pub trait BalancerSorApi: Send + Sync + 'static {
 pub struct DefaultBalancerSorApi {
     client: Client,
     url: Url,
+    retry_policy: HttpRetryPolicy,
 }
 
 impl DefaultBalancerSorApi {
@@ -35,7 +37,17 @@ impl DefaultBalancerSorApi {
         );
 
         let url = base_url.into_url()?.join(&chain_id.to_string())?;
-        Ok(Self { client, url })
+        Ok(Self {
+            client,
+            url,
+            retry_policy: Default::default(),
+        })
+    }
+
+    /// Retries failed requests according to the specified policy.
+    pub fn with_retry_policy(mut self, retry_policy: HttpRetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
     }
 }
 
@@ -43,11 +55,10 @@ impl DefaultBalancerSorApi {
 impl BalancerSorApi for DefaultBalancerSorApi {
     async fn quote(&self, query: Query) -> Result<Option<Quote>> {
         tracing::debug!(url =% self.url, ?query, "querying Balancer SOR");
+        let request = self.client.post(self.url.clone()).json(&query).build()?;
         let response = self
-            .client
-            .post(self.url.clone())
-            .json(&query)
-            .send()
+            .retry_policy
+            .execute_idempotent(&self.client, request)
             .await?
             .text()
             .await?;
@@ -0,0 +1,154 @@
+//! Retrying of outgoing http requests on transient errors.
+
+use reqwest::{Client, Method, Request, Response, StatusCode};
+use std::time::Duration;
+
+/// Policy for retrying idempotent http requests that fail because of connection errors, timeouts or
+/// server errors. Each retry waits twice as long as the previous one, starting with the base
+/// backoff.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HttpRetryPolicy {
+    pub max_retries: u32,
+    pub base_backoff: Duration,
+}
+
+impl HttpRetryPolicy {
+    pub fn new(max_retries: u32, base_backoff: Duration) -> Self {
+        Self {
+            max_retries,
+            base_backoff,
+        }
+    }
+
+    /// Executes the request with the client, retrying according to the policy. Requests with
+    /// non-idempotent methods or bodies that can't be cloned are executed once.
+    pub async fn execute(&self, client: &Client, request: Request) -> reqwest::Result<Response> {
+        if !is_idempotent(request.method()) {
+            return client.execute(request).await;
+        }
+        self.execute_idempotent(client, request).await
+    }
+
+    /// Like `execute` but also retries requests with non-idempotent methods. Meant for queries
+    /// that are sent as POST but don't have side effects, like quote or solve requests.
+    pub async fn execute_idempotent(
+        &self,
+        client: &Client,
+        request: Request,
+    ) -> reqwest::Result<Response> {
+        let mut retries = 0;
+        loop {
+            let retry_request = match request.try_clone() {
+                Some(retry_request) if retries < self.max_retries => retry_request,
+                _ => return client.execute(request).await,
+            };
+            let result = client.execute(retry_request).await;
+            if !should_retry(&result) {
+                return result;
+            }
+            tracing::debug!(url = %request.url(), retries, "retrying http request");
+            tokio::time::sleep(self.base_backoff * 2u32.pow(retries.min(16))).await;
+            retries += 1;
+        }
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE | Method::TRACE
+    )
+}
+
+fn should_retry(result: &reqwest::Result<Response>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(err) => {
+            err.is_connect()
+                || err.is_timeout()
+                || matches!(err.status(), Some(status) if status >= StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+    };
+    use warp::Filter;
+
+    /// Serves requests that fail with a server error until `failures` requests were made.
+    fn flaky_server(failures: u32) -> (SocketAddr, Arc<AtomicU32>) {
+        let requests = Arc::new(AtomicU32::new(0));
+        let filter = warp::any().map({
+            let requests = requests.clone();
+            move || {
+                let status = if requests.fetch_add(1, Ordering::SeqCst) < failures {
+                    warp::http::StatusCode::INTERNAL_SERVER_ERROR
+                } else {
+                    warp::http::StatusCode::OK
+                };
+                warp::reply::with_status("response", status)
+            }
+        });
+        let (address, server) = warp::serve(filter).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        (address, requests)
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors_until_success() {
+        let (address, requests) = flaky_server(2);
+        let client = Client::new();
+        let policy = HttpRetryPolicy::new(3, Duration::from_millis(1));
+
+        let request = client.get(format!("http://{}", address)).build().unwrap();
+        let response = policy.execute(&client, request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "response");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let (address, requests) = flaky_server(10);
+        let client = Client::new();
+        let policy = HttpRetryPolicy::new(2, Duration::from_millis(1));
+
+        let request = client.get(format!("http://{}", address)).build().unwrap();
+        let response = policy.execute(&client, request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_post_requests() {
+        let (address, requests) = flaky_server(1);
+        let client = Client::new();
+        let policy = HttpRetryPolicy::new(2, Duration::from_millis(1));
+
+        let request = client.post(format!("http://{}", address)).build().unwrap();
+        let response = policy.execute(&client, request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let request = client.post(format!("http://{}", address)).build().unwrap();
+        let response = policy.execute_idempotent(&client, request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn only_retries_idempotent_methods() {
+        assert!(is_idempotent(&Method::GET));
+        assert!(is_idempotent(&Method::PUT));
+        assert!(!is_idempotent(&Method::POST));
+        assert!(!is_idempotent(&Method::PATCH));
+    }
+}
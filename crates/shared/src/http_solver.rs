@@ -1,3 +1,4 @@
+use crate::http_retry::HttpRetryPolicy;
 use anyhow::{anyhow, ensure, Context, Result};
 use reqwest::header::HeaderValue;
 use reqwest::{Client, Url};
@@ -40,6 +41,9 @@ pub struct DefaultHttpSolverApi {
     /// An async HTTP client instance that will be used to interact with the solver.
     pub client: Client,
 
+    /// Policy for retrying requests that failed because of connection or server errors.
+    pub retry_policy: HttpRetryPolicy,
+
     /// Other solver parameters.
     pub config: SolverConfig,
 }
@@ -114,8 +118,15 @@ impl HttpSolverApi for DefaultHttpSolverApi {
         }
         let body = serde_json::to_string(&model).context("failed to encode body")?;
         tracing::trace!("request {}", body);
-        let request = request.body(body.clone());
-        let response = request.send().await.context("failed to send request")?;
+        let request = request
+            .body(body.clone())
+            .build()
+            .context("failed to build request")?;
+        let response = self
+            .retry_policy
+            .execute_idempotent(&self.client, request)
+            .await
+            .context("failed to send request")?;
         let status = response.status();
         let text = response
             .text()
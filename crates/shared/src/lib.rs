@@ -10,6 +10,7 @@ pub mod current_block;
 pub mod ethcontract_error;
 pub mod event_handling;
 pub mod gas_price_estimation;
+pub mod http_retry;
 pub mod http_solver;
 pub mod maintenance;
 pub mod metrics;
@@ -47,12 +48,26 @@ pub type Web3 = DynWeb3;
 pub type Web3CallBatch = CallBatch<Web3Transport>;
 
 /// The standard http client we use in the api and driver.
-pub fn http_client(timeout: Duration) -> reqwest::Client {
-    reqwest::ClientBuilder::new()
-        .timeout(timeout)
-        .user_agent("cowprotocol-services/2.0.0")
-        .build()
-        .unwrap()
+pub fn http_client(args: &arguments::Arguments) -> reqwest::Client {
+    let headers = args
+        .http_headers
+        .iter()
+        .map(|header| (header.name.clone(), header.value.clone()))
+        .collect::<reqwest::header::HeaderMap>();
+    let mut builder = reqwest::ClientBuilder::new()
+        .timeout(args.http_timeout)
+        .user_agent(args.http_user_agent.as_str())
+        .default_headers(headers);
+    if let Some(proxy) = &args.http_proxy {
+        // Credentials in the URL are used for basic authentication with the proxy.
+        builder = builder.proxy(reqwest::Proxy::all(proxy.clone()).expect("invalid http proxy"));
+    }
+    builder.build().unwrap()
+}
+
+/// The retry policy for outgoing http requests of the APIs that use the shared http client.
+pub fn http_retry_policy(args: &arguments::Arguments) -> http_retry::HttpRetryPolicy {
+    http_retry::HttpRetryPolicy::new(args.http_max_retries, args.http_retry_backoff)
 }
 
 /// Run a future and callback with the time the future took. The call back can for example log the
@@ -1,8 +1,11 @@
 use once_cell::sync::OnceCell;
-use prometheus::Encoder;
+use prometheus::{proto::MetricType, Encoder};
 use std::collections::HashMap;
-use std::{convert::Infallible, net::SocketAddr, sync::Arc};
-use tokio::task::{self, JoinHandle};
+use std::{convert::Infallible, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    net::UdpSocket,
+    task::{self, JoinHandle},
+};
 use warp::{Filter, Rejection, Reply};
 
 pub const DEFAULT_METRICS_PORT: u16 = 9586;
@@ -60,6 +63,83 @@ pub fn serve_metrics(liveness: Arc<dyn LivenessChecking>, address: SocketAddr) -
     task::spawn(warp::serve(filter).bind(address))
 }
 
+/// Periodically pushes all metrics of the global registry to a StatsD server at the specified
+/// `host:port` address. Labels are sent as DogStatsD tags.
+///
+/// Since prometheus counters are cumulative all values are sent as gauges. Histograms and summaries
+/// are sent as their sample count and sum.
+pub fn push_metrics_to_statsd(address: String, interval: Duration) -> JoinHandle<()> {
+    let registry = get_metrics_registry();
+    tracing::info!(%address, "pushing metrics to statsd");
+    task::spawn(async move {
+        let socket = match UdpSocket::bind("0.0.0.0:0").await {
+            Ok(socket) => socket,
+            Err(err) => {
+                tracing::error!(?err, "could not bind statsd socket");
+                return;
+            }
+        };
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(err) = socket.connect(&address).await {
+                tracing::warn!(?err, "could not connect to statsd at {}", address);
+                continue;
+            }
+            for line in encode_statsd(&registry.gather()) {
+                if let Err(err) = socket.send(line.as_bytes()).await {
+                    tracing::warn!(?err, "could not send metrics to statsd");
+                    break;
+                }
+            }
+        }
+    })
+}
+
+/// Encodes prometheus metric families as StatsD gauge lines.
+fn encode_statsd(families: &[prometheus::proto::MetricFamily]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for family in families {
+        let name = family.get_name();
+        for metric in family.get_metric() {
+            let tags = metric
+                .get_label()
+                .iter()
+                .map(|label| format!("{}:{}", label.get_name(), label.get_value()))
+                .collect::<Vec<_>>()
+                .join(",");
+            let mut push = |name: &str, value: f64| {
+                if tags.is_empty() {
+                    lines.push(format!("{}:{}|g", name, value));
+                } else {
+                    lines.push(format!("{}:{}|g|#{}", name, value, tags));
+                }
+            };
+            match family.get_field_type() {
+                MetricType::COUNTER => push(name, metric.get_counter().get_value()),
+                MetricType::GAUGE => push(name, metric.get_gauge().get_value()),
+                MetricType::UNTYPED => push(name, metric.get_untyped().get_value()),
+                MetricType::HISTOGRAM => {
+                    let histogram = metric.get_histogram();
+                    push(
+                        &format!("{}_count", name),
+                        histogram.get_sample_count() as f64,
+                    );
+                    push(&format!("{}_sum", name), histogram.get_sample_sum());
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.get_summary();
+                    push(
+                        &format!("{}_count", name),
+                        summary.get_sample_count() as f64,
+                    );
+                    push(&format!("{}_sum", name), summary.get_sample_sum());
+                }
+            }
+        }
+    }
+    lines
+}
+
 // `/metrics` route exposing encoded prometheus data to monitoring system
 pub fn handle_metrics() -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     let registry = get_metrics_registry();
@@ -94,3 +174,31 @@ fn handle_liveness(
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{Histogram, HistogramOpts, IntCounterVec, Opts, Registry};
+
+    #[test]
+    fn encodes_statsd_lines() {
+        let registry = Registry::new();
+        let counter = IntCounterVec::new(Opts::new("requests", "help"), &["method"]).unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        let histogram = Histogram::with_opts(HistogramOpts::new("latency", "help")).unwrap();
+        registry.register(Box::new(histogram.clone())).unwrap();
+
+        counter.with_label_values(&["get"]).inc_by(3);
+        histogram.observe(0.5);
+        histogram.observe(1.5);
+
+        assert_eq!(
+            encode_statsd(&registry.gather()),
+            vec![
+                "latency_count:2|g".to_string(),
+                "latency_sum:2|g".to_string(),
+                "requests:3|g|#method:get".to_string(),
+            ]
+        );
+    }
+}
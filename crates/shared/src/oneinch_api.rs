@@ -4,6 +4,7 @@
 //! <https://docs.1inch.io/docs/aggregation-protocol/api/swagger>
 //! Although there is no documentation about API v4.1, it exists and is identical to v4.0 except it
 //! uses EIP 1559 gas prices.
+use crate::http_retry::HttpRetryPolicy;
 use crate::solver_utils::{deserialize_prefixed_hex, Slippage};
 use anyhow::{ensure, Context, Result};
 use cached::{Cached, TimedCache};
@@ -433,6 +434,7 @@ pub struct OneInchClientImpl {
     client: Client,
     base_url: Url,
     chain_id: u64,
+    retry_policy: HttpRetryPolicy,
 }
 
 impl OneInchClientImpl {
@@ -452,21 +454,46 @@ impl OneInchClientImpl {
             client,
             base_url: base_url.into_url()?,
             chain_id,
+            retry_policy: Default::default(),
         })
     }
+
+    /// Retries failed requests according to the specified policy.
+    pub fn with_retry_policy(mut self, retry_policy: HttpRetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    async fn logged_query<D>(&self, url: Url) -> Result<D>
+    where
+        D: for<'de> Deserialize<'de>,
+    {
+        tracing::debug!("Query 1inch API for url {}", url);
+        let request = self.client.get(url).build()?;
+        let response = self
+            .retry_policy
+            .execute(&self.client, request)
+            .await?
+            .text()
+            .await;
+        tracing::debug!("Response from 1inch API: {:?}", response);
+        serde_json::from_str(&response?).context("1inch result parsing failed")
+    }
 }
 
 #[async_trait::async_trait]
 impl OneInchClient for OneInchClientImpl {
     async fn get_swap(&self, query: SwapQuery) -> Result<RestResponse<Swap>> {
-        logged_query(&self.client, query.into_url(&self.base_url, self.chain_id)).await
+        self.logged_query(query.into_url(&self.base_url, self.chain_id))
+            .await
     }
 
     async fn get_sell_order_quote(
         &self,
         query: SellOrderQuoteQuery,
     ) -> Result<RestResponse<SellOrderQuote>> {
-        logged_query(&self.client, query.into_url(&self.base_url, self.chain_id)).await
+        self.logged_query(query.into_url(&self.base_url, self.chain_id))
+            .await
     }
 
     async fn get_spender(&self) -> Result<Spender> {
@@ -475,7 +502,7 @@ impl OneInchClient for OneInchClientImpl {
             .base_url
             .join(&endpoint)
             .expect("unexpectedly invalid URL");
-        logged_query(&self.client, url).await
+        self.logged_query(url).await
     }
 
     async fn get_liquidity_sources(&self) -> Result<Protocols> {
@@ -484,20 +511,10 @@ impl OneInchClient for OneInchClientImpl {
             .base_url
             .join(&endpoint)
             .expect("unexpectedly invalid URL");
-        logged_query(&self.client, url).await
+        self.logged_query(url).await
     }
 }
 
-async fn logged_query<D>(client: &Client, url: Url) -> Result<D>
-where
-    D: for<'de> Deserialize<'de>,
-{
-    tracing::debug!("Query 1inch API for url {}", url);
-    let response = client.get(url).send().await?.text().await;
-    tracing::debug!("Response from 1inch API: {:?}", response);
-    serde_json::from_str(&response?).context("1inch result parsing failed")
-}
-
 #[derive(Debug, Clone)]
 pub struct ProtocolCache(Arc<Mutex<TimedCache<(), Vec<ProtocolInfo>>>>);
 
@@ -1,4 +1,5 @@
 use crate::debug_bytes;
+use crate::http_retry::HttpRetryPolicy;
 use anyhow::Result;
 use derivative::Derivative;
 use ethcontract::{H160, U256};
@@ -28,6 +29,7 @@ pub trait ParaswapApi: Send + Sync {
 pub struct DefaultParaswapApi {
     pub client: Client,
     pub partner: String,
+    pub retry_policy: HttpRetryPolicy,
 }
 
 #[async_trait::async_trait]
@@ -35,7 +37,13 @@ impl ParaswapApi for DefaultParaswapApi {
     async fn price(&self, query: PriceQuery) -> Result<PriceResponse, ParaswapResponseError> {
         let url = query.into_url(&self.partner);
         tracing::debug!("Querying Paraswap price API: {}", url);
-        let response_text = self.client.get(url).send().await?.text().await?;
+        let request = self.client.get(url).build()?;
+        let response_text = self
+            .retry_policy
+            .execute(&self.client, request)
+            .await?
+            .text()
+            .await?;
         tracing::debug!("Response from Paraswap price API: {}", response_text);
         parse_paraswap_response_text(&response_text)
     }
@@ -47,9 +55,10 @@ impl ParaswapApi for DefaultParaswapApi {
             query,
             partner: &self.partner,
         };
-        let response_text = query
-            .into_request(&self.client)
-            .send()
+        let request = query.into_request(&self.client).build()?;
+        let response_text = self
+            .retry_policy
+            .execute_idempotent(&self.client, request)
             .await?
             .text()
             .await?;
@@ -725,6 +734,7 @@ mod tests {
         let api = DefaultParaswapApi {
             client: Client::new(),
             partner: "Test".into(),
+            retry_policy: Default::default(),
         };
 
         let good_query = TransactionBuilderQuery {
@@ -2,7 +2,7 @@ use crate::price_estimation::{PriceEstimating, PriceEstimationError, Query};
 use futures::{stream::BoxStream, StreamExt};
 use model::order::OrderKind;
 use primitive_types::{H160, U256};
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 pub type NativePriceEstimateResult = Result<f64, PriceEstimationError>;
 
@@ -25,6 +25,7 @@ pub struct NativePriceEstimator {
     inner: Arc<dyn PriceEstimating>,
     native_token: H160,
     price_estimation_amount: U256,
+    price_estimation_amount_overrides: HashMap<H160, U256>,
 }
 
 impl NativePriceEstimator {
@@ -37,14 +38,26 @@ impl NativePriceEstimator {
             inner,
             native_token,
             price_estimation_amount,
+            price_estimation_amount_overrides: Default::default(),
         }
     }
 
+    /// Uses the specified amounts instead of the default one when estimating the price of the
+    /// respective tokens.
+    pub fn with_amount_overrides(mut self, overrides: HashMap<H160, U256>) -> Self {
+        self.price_estimation_amount_overrides = overrides;
+        self
+    }
+
     fn query(&self, token: &H160) -> Query {
         Query {
             sell_token: *token,
             buy_token: self.native_token,
-            in_amount: self.price_estimation_amount,
+            in_amount: self
+                .price_estimation_amount_overrides
+                .get(token)
+                .copied()
+                .unwrap_or(self.price_estimation_amount),
             kind: OrderKind::Buy,
         }
     }
@@ -121,6 +134,7 @@ mod tests {
             inner: Arc::new(inner),
             native_token: H160::from_low_u64_be(7),
             price_estimation_amount: U256::exp10(18),
+            price_estimation_amount_overrides: Default::default(),
         };
 
         let result = native_price_estimator
@@ -149,6 +163,7 @@ mod tests {
             inner: Arc::new(inner),
             native_token: H160::from_low_u64_be(7),
             price_estimation_amount: U256::exp10(18),
+            price_estimation_amount_overrides: Default::default(),
         };
 
         let result = native_price_estimator
@@ -160,4 +175,26 @@ mod tests {
             .1;
         assert!(matches!(result, Err(PriceEstimationError::NoLiquidity)));
     }
+
+    #[test]
+    fn uses_amount_overrides() {
+        let token = H160::from_low_u64_be(3);
+        let native_price_estimator = NativePriceEstimator::new(
+            Arc::new(MockPriceEstimating::new()),
+            H160::from_low_u64_be(7),
+            U256::exp10(18),
+        )
+        .with_amount_overrides(maplit::hashmap! { token => U256::exp10(15) });
+
+        assert_eq!(
+            native_price_estimator.query(&token).in_amount,
+            U256::exp10(15)
+        );
+        assert_eq!(
+            native_price_estimator
+                .query(&H160::from_low_u64_be(4))
+                .in_amount,
+            U256::exp10(18)
+        );
+    }
 }
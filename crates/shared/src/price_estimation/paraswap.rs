@@ -156,6 +156,7 @@ mod tests {
         let paraswap = DefaultParaswapApi {
             client: Client::new(),
             partner: "".to_string(),
+            retry_policy: Default::default(),
         };
         let estimator = ParaswapPriceEstimator {
             paraswap: Arc::new(paraswap),
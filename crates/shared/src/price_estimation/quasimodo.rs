@@ -432,6 +432,7 @@ mod tests {
                 chain_id: 1,
                 base: Url::parse(&quasimodo_url).expect("failed to parse quasimodo url"),
                 client,
+                retry_policy: Default::default(),
                 config: SolverConfig {
                     api_key: None,
                     max_nr_exec_orders: 100,
@@ -32,6 +32,26 @@ pub enum BaselineSource {
     ZeroEx,
 }
 
+impl BaselineSource {
+    /// Returns whether the contracts of the source are deployed on the chain or `None` if the
+    /// chain is not known.
+    pub fn is_deployed_on_chain(self, chain_id: u64) -> Option<bool> {
+        if !matches!(chain_id, 1 | 4 | 100) {
+            return None;
+        }
+        let chains: &[u64] = match self {
+            BaselineSource::UniswapV2 => &[1, 4],
+            BaselineSource::Honeyswap => &[100],
+            BaselineSource::SushiSwap => &[1, 4, 100],
+            BaselineSource::BalancerV2 => &[1, 4],
+            BaselineSource::Baoswap => &[100],
+            BaselineSource::Swapr => &[100],
+            BaselineSource::ZeroEx => &[1],
+        };
+        Some(chains.contains(&chain_id))
+    }
+}
+
 pub fn defaults_for_chain(chain_id: u64) -> Result<Vec<BaselineSource>> {
     Ok(match chain_id {
         1 => vec![
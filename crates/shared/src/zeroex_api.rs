@@ -5,6 +5,7 @@
 //! <https://api.0x.org/>
 
 use crate::debug_bytes;
+use crate::http_retry::HttpRetryPolicy;
 use crate::solver_utils::{deserialize_decimal_f64, Slippage};
 use anyhow::{Context, Result};
 use chrono::{DateTime, NaiveDateTime, Utc};
@@ -293,6 +294,7 @@ pub struct DefaultZeroExApi {
     client: Client,
     base_url: Url,
     api_key: Option<String>,
+    retry_policy: HttpRetryPolicy,
 }
 
 impl DefaultZeroExApi {
@@ -310,9 +312,16 @@ impl DefaultZeroExApi {
             client,
             base_url: base_url.into_url().context("zeroex api url")?,
             api_key,
+            retry_policy: Default::default(),
         })
     }
 
+    /// Retries failed requests according to the specified policy.
+    pub fn with_retry_policy(mut self, retry_policy: HttpRetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
     /// Create a new 0x HTTP API client using the default URL.
     pub fn with_default_url(client: Client) -> Self {
         Self::new(Self::DEFAULT_URL, None, client).unwrap()
@@ -432,8 +441,10 @@ impl DefaultZeroExApi {
         if let Some(key) = &self.api_key {
             request = request.header("0x-api-key", key);
         }
-        let response_text = request
-            .send()
+        let request = request.build().map_err(ZeroExResponseError::Send)?;
+        let response_text = self
+            .retry_policy
+            .execute(&self.client, request)
             .await
             .map_err(ZeroExResponseError::Send)?
             .text()
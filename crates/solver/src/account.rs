@@ -0,0 +1,106 @@
+//! Helpers for loading solver accounts.
+
+use anyhow::{anyhow, Context as _, Result};
+use eth_keystore::KeystoreError;
+use ethcontract::{Account, PrivateKey};
+use std::path::Path;
+
+/// Loads the private key stored in the encrypted JSON keystore at `path`. The passphrase for the
+/// keystore is read from the environment variable `passphrase_env`.
+pub fn private_key_from_keystore(path: &Path, passphrase_env: &str) -> Result<PrivateKey> {
+    let passphrase = std::env::var(passphrase_env)
+        .with_context(|| format!("missing keystore passphrase in {}", passphrase_env))?;
+    let key = eth_keystore::decrypt_key(path, passphrase).map_err(|err| match err {
+        KeystoreError::MacMismatch => anyhow!("invalid passphrase for keystore {:?}", path),
+        err => anyhow!(err).context(format!("failed to decrypt keystore {:?}", path)),
+    })?;
+    PrivateKey::from_slice(&key).map_err(|err| anyhow!("invalid private key in keystore: {}", err))
+}
+
+/// Creates an offline account from the encrypted JSON keystore at `path`. See
+/// [`private_key_from_keystore`].
+pub fn account_from_keystore(
+    path: &Path,
+    passphrase_env: &str,
+    chain_id: Option<u64>,
+) -> Result<Account> {
+    let key = private_key_from_keystore(path, passphrase_env)?;
+    Ok(Account::Offline(key, chain_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethcontract::H160;
+    use hex_literal::hex;
+    use std::path::PathBuf;
+
+    // Keystore for the private key 0x4c08...2318 encrypted with the passphrase
+    // "keystore test passphrase".
+    const KEYSTORE: &str = r#"{
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "101112131415161718191a1b1c1d1e1f" },
+            "ciphertext": "dab0bed6a939532b2b4307be88e287dfbd0b4fdd2cc2b52ef179917744edd66e",
+            "kdf": "pbkdf2",
+            "kdfparams": {
+                "c": 1024,
+                "dklen": 32,
+                "prf": "hmac-sha256",
+                "salt": "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+            },
+            "mac": "c961746b8c728d1d631fbf84f2418110b54b02953721e3d40c17a701d6c82fa2"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    fn write_keystore(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}_{}.json", name, std::process::id()));
+        std::fs::write(&path, KEYSTORE).unwrap();
+        path
+    }
+
+    #[test]
+    fn errors_without_passphrase() {
+        let result = account_from_keystore(
+            Path::new("/does/not/exist.json"),
+            "SOLVER_KEYSTORE_TEST_MISSING_PASSPHRASE",
+            None,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn errors_with_wrong_passphrase() {
+        let path = write_keystore("keystore_wrong_passphrase");
+        std::env::set_var(
+            "SOLVER_KEYSTORE_TEST_WRONG_PASSPHRASE",
+            "not the passphrase",
+        );
+        let result = account_from_keystore(&path, "SOLVER_KEYSTORE_TEST_WRONG_PASSPHRASE", None);
+        std::fs::remove_file(&path).unwrap();
+        let err = result.unwrap_err();
+        assert!(
+            err.to_string().contains("invalid passphrase"),
+            "unexpected error: {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn decrypts_keystore() {
+        let path = write_keystore("keystore_decrypt");
+        std::env::set_var(
+            "SOLVER_KEYSTORE_TEST_PASSPHRASE",
+            "keystore test passphrase",
+        );
+        let account =
+            account_from_keystore(&path, "SOLVER_KEYSTORE_TEST_PASSPHRASE", Some(1)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            account.address(),
+            H160(hex!("2c7536e3605d9c16a7a3d7b1898e529396a65c23"))
+        );
+    }
+}
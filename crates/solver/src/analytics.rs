@@ -24,24 +24,28 @@ pub fn report_matched_but_not_settled(
         .iter()
         .map(|order_trade| order_trade.trade.order.metadata.uid)
         .collect();
-    let other_matched_orders: HashSet<_> = alternative_settlements
+    let other_matched_orders: HashMap<_, _> = alternative_settlements
         .iter()
         .flat_map(|(_, solution)| solution.settlement.encoder.order_trades().to_vec())
-        .map(|order_trade| order_trade.trade.order.metadata.uid)
+        .map(|order_trade| {
+            let order = &order_trade.trade.order;
+            (order.metadata.uid, order.creation.kind)
+        })
         .collect();
-    let matched_but_not_settled: HashSet<_> = other_matched_orders
-        .difference(&submitted_orders)
-        .copied()
+    let matched_but_not_settled: HashMap<_, _> = other_matched_orders
+        .into_iter()
+        .filter(|(uid, _)| !submitted_orders.contains(uid))
         .collect();
 
     if !matched_but_not_settled.is_empty() {
         tracing::debug!(
-            ?matched_but_not_settled,
+            matched_but_not_settled = ?matched_but_not_settled.keys().collect::<Vec<_>>(),
             "some orders were matched but not settled"
         );
     }
 
-    metrics.orders_matched_but_not_settled(matched_but_not_settled.len());
+    metrics
+        .orders_matched_but_not_settled(&matched_but_not_settled.into_values().collect::<Vec<_>>());
 }
 
 #[derive(Clone)]
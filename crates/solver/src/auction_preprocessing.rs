@@ -1,9 +1,131 @@
 //! Submodule containing helper methods to pre-process auction data before passing it on to the solvers.
 
-use crate::liquidity::LimitOrder;
+use crate::liquidity::{LimitOrder, Liquidity};
+use anyhow::{ensure, Result};
+use primitive_types::{H160, U256};
+use std::{cmp::Reverse, collections::HashSet};
 
 // vk: I would like to extend this to also check that the order has minimum age but for this we need
 // access to the creation date which is a more involved change.
 pub fn has_at_least_one_user_order(orders: &[LimitOrder]) -> bool {
     orders.iter().any(|order| !order.is_liquidity_order)
 }
+
+/// Gas prices above this value in wei (100k gwei) are considered to be an error of the gas price
+/// estimator.
+const MAX_GAS_PRICE: f64 = 1e14;
+
+/// Checks that the gas price in wei is sensible so that solvers don't get an auction with a gas
+/// price that is obviously wrong, for example because it is denominated in gwei.
+pub fn validate_gas_price(gas_price: f64) -> Result<f64> {
+    ensure!(
+        gas_price.is_finite() && gas_price > 0.,
+        "invalid gas price {}",
+        gas_price
+    );
+    ensure!(
+        gas_price <= MAX_GAS_PRICE,
+        "gas price {} exceeds the maximum of {}",
+        gas_price,
+        MAX_GAS_PRICE
+    );
+    Ok(gas_price)
+}
+
+/// Returns a predicate that checks whether liquidity trades any of the tokens of the orders.
+pub fn trades_order_token(orders: &[LimitOrder]) -> impl Fn(&Liquidity) -> bool {
+    let order_tokens = orders
+        .iter()
+        .flat_map(|order| [order.sell_token, order.buy_token])
+        .collect::<HashSet<H160>>();
+    move |liquidity: &Liquidity| {
+        liquidity.all_token_pairs().iter().any(|pair| {
+            let (token0, token1) = pair.get();
+            order_tokens.contains(&token0) || order_tokens.contains(&token1)
+        })
+    }
+}
+
+/// Keeps at most `max_liquidity` liquidity entries. Liquidity that trades a token of one of the
+/// orders is kept first, the rest by the size of its largest reserve.
+pub fn limit_liquidity(
+    mut liquidity: Vec<Liquidity>,
+    orders: &[LimitOrder],
+    max_liquidity: usize,
+) -> Vec<Liquidity> {
+    if liquidity.len() <= max_liquidity {
+        return liquidity;
+    }
+    let trades_order_token = trades_order_token(orders);
+    liquidity.sort_by_cached_key(|liquidity| {
+        Reverse((trades_order_token(liquidity), largest_reserve(liquidity)))
+    });
+    liquidity.truncate(max_liquidity);
+    liquidity
+}
+
+fn largest_reserve(liquidity: &Liquidity) -> U256 {
+    match liquidity {
+        Liquidity::ConstantProduct(amm) => amm.reserves.0.max(amm.reserves.1).into(),
+        Liquidity::BalancerWeighted(amm) => amm
+            .reserves
+            .values()
+            .map(|state| state.common.balance)
+            .max()
+            .unwrap_or_default(),
+        Liquidity::BalancerStable(amm) => amm
+            .reserves
+            .values()
+            .map(|state| state.balance)
+            .max()
+            .unwrap_or_default(),
+        Liquidity::LimitOrder(order) => order.sell_amount,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::liquidity::{tests::CapturingSettlementHandler, ConstantProductOrder};
+    use model::TokenPair;
+    use num::rational::Ratio;
+
+    #[test]
+    fn rejects_invalid_gas_prices() {
+        assert_eq!(validate_gas_price(30e9).unwrap(), 30e9);
+        assert_eq!(validate_gas_price(MAX_GAS_PRICE).unwrap(), MAX_GAS_PRICE);
+        for gas_price in [0., -1e9, f64::NAN, f64::INFINITY, MAX_GAS_PRICE * 2.] {
+            assert!(validate_gas_price(gas_price).is_err(), "{}", gas_price);
+        }
+    }
+
+    #[test]
+    fn limit_liquidity_keeps_order_relevant_and_large_pools() {
+        let token = H160::from_low_u64_be;
+        let pool = |token0: u64, token1: u64, reserve: u128| {
+            Liquidity::ConstantProduct(ConstantProductOrder {
+                tokens: TokenPair::new(token(token0), token(token1)).unwrap(),
+                reserves: (reserve, reserve),
+                fee: Ratio::new(3, 1000),
+                settlement_handling: CapturingSettlementHandler::arc(),
+            })
+        };
+        let orders = [LimitOrder {
+            sell_token: token(1),
+            buy_token: token(2),
+            ..Default::default()
+        }];
+        let liquidity = vec![
+            pool(3, 4, 100),
+            pool(1, 3, 1),
+            pool(4, 5, 1000),
+            pool(5, 6, 10),
+        ];
+
+        assert_eq!(
+            limit_liquidity(liquidity.clone(), &orders, 2),
+            [pool(1, 3, 1), pool(4, 5, 1000)]
+        );
+        assert_eq!(limit_liquidity(liquidity.clone(), &orders, 4), liquidity);
+    }
+}
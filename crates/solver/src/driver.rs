@@ -12,7 +12,7 @@ use crate::{
     settlement_post_processing::PostProcessingPipeline,
     settlement_simulation::{self, settle_method, simulate_before_after_access_list, TenderlyApi},
     settlement_submission::SolutionSubmitter,
-    solver::{Auction, SettlementWithError, SettlementWithSolver, Solver, Solvers},
+    solver::{Auction, FeePolicy, SettlementWithError, SettlementWithSolver, Solver, Solvers},
 };
 use anyhow::{Context, Result};
 use contracts::GPv2Settlement;
@@ -61,6 +61,8 @@ pub struct Driver {
     max_settlement_price_deviation: Option<Ratio<BigInt>>,
     token_list_restriction_for_price_checks: PriceCheckTokens,
     tenderly: Option<TenderlyApi>,
+    fee_policy: FeePolicy,
+    max_liquidity: Option<usize>,
 }
 impl Driver {
     #[allow(clippy::too_many_arguments)]
@@ -89,6 +91,8 @@ impl Driver {
         max_settlement_price_deviation: Option<Ratio<BigInt>>,
         token_list_restriction_for_price_checks: PriceCheckTokens,
         tenderly: Option<TenderlyApi>,
+        fee_policy: FeePolicy,
+        max_liquidity: Option<usize>,
     ) -> Self {
         let post_processing_pipeline = PostProcessingPipeline::new(
             native_token,
@@ -125,16 +129,24 @@ impl Driver {
             max_settlement_price_deviation,
             token_list_restriction_for_price_checks,
             tenderly,
+            fee_policy,
+            max_liquidity,
         }
     }
 
     pub async fn run_forever(&mut self) -> ! {
         loop {
-            match self.single_run().await {
-                Ok(()) => tracing::debug!("single run finished ok"),
-                Err(err) => tracing::error!("single run errored: {:?}", err),
-            }
-            self.metrics.runloop_completed();
+            let settlement_submitted = match self.single_run().await {
+                Ok(settlement_submitted) => {
+                    tracing::debug!("single run finished ok");
+                    settlement_submitted
+                }
+                Err(err) => {
+                    tracing::error!("single run errored: {:?}", err);
+                    false
+                }
+            };
+            self.metrics.runloop_completed(settlement_submitted);
             tokio::time::sleep(self.settle_interval).await;
         }
     }
@@ -171,6 +183,12 @@ impl Driver {
     ) -> Result<TransactionReceipt> {
         let settlement = rated_settlement.settlement;
         let traded_orders = settlement.traded_orders().cloned().collect::<Vec<_>>();
+        let interaction_kinds = settlement
+            .encoder
+            .execution_plan()
+            .iter()
+            .map(|interaction| interaction.kind())
+            .collect::<Vec<_>>();
 
         self.metrics
             .settlement_revertable_status(settlement.revertable(), solver.name());
@@ -195,6 +213,12 @@ impl Driver {
                 traded_orders
                     .iter()
                     .for_each(|order| self.metrics.order_settled(order, name));
+                interaction_kinds
+                    .iter()
+                    .for_each(|kind| self.metrics.interaction_used(kind, name));
+                if let Some(gas_used) = receipt.gas_used {
+                    self.metrics.settlement_gas_used(gas_used, name);
+                }
                 self.metrics.settlement_submitted(
                     crate::metrics::SettlementSubmissionOutcome::Success,
                     name,
@@ -236,13 +260,7 @@ impl Driver {
         )
         .await?;
         tracing::debug!(?gas_saved, "access list gas saved");
-        if gas_saved.is_sign_positive() {
-            self.metrics
-                .settlement_access_list_saved_gas(gas_saved, "positive");
-        } else {
-            self.metrics
-                .settlement_access_list_saved_gas(-gas_saved, "negative");
-        }
+        self.metrics.settlement_access_list_saved_gas(gas_saved);
 
         Ok(())
     }
@@ -408,7 +426,8 @@ impl Driver {
         )
     }
 
-    pub async fn single_run(&mut self) -> Result<()> {
+    /// Runs a single auction. Returns whether a settlement was submitted.
+    pub async fn single_run(&mut self) -> Result<bool> {
         let start = Instant::now();
         tracing::debug!("starting single run");
 
@@ -458,9 +477,21 @@ impl Driver {
         self.metrics.liquidity_fetched(&liquidity);
 
         if !auction_preprocessing::has_at_least_one_user_order(&orders) {
-            return Ok(());
+            return Ok(false);
         }
 
+        let liquidity = match self.max_liquidity {
+            Some(max_liquidity) => {
+                let liquidity_count = liquidity.len();
+                let liquidity =
+                    auction_preprocessing::limit_liquidity(liquidity, &orders, max_liquidity);
+                self.metrics
+                    .liquidity_dropped(liquidity_count - liquidity.len());
+                liquidity
+            }
+            None => liquidity,
+        };
+
         let gas_price = self
             .gas_price_estimator
             .estimate()
@@ -475,9 +506,10 @@ impl Driver {
             id: auction_id,
             orders: orders.clone(),
             liquidity,
-            gas_price: gas_price.effective_gas_price(),
+            gas_price: auction_preprocessing::validate_gas_price(gas_price.effective_gas_price())?,
             deadline: Instant::now() + self.solver_time_limit,
             external_prices: external_prices.clone(),
+            fee_policy: self.fee_policy,
         };
         tracing::debug!("solving auction id {}", auction.id);
         let run_solver_results = self.run_solvers(auction).await;
@@ -601,6 +633,7 @@ impl Driver {
 
         rated_settlements.sort_by(|a, b| a.1.objective_value().cmp(&b.1.objective_value()));
         print_settlements(&rated_settlements, &self.fee_objective_scaling_factor);
+        let mut settlement_submitted = false;
         if let Some((winning_solver, mut winning_settlement, access_list)) = rated_settlements.pop()
         {
             // If we have enough buffer in the settlement contract to not use on-chain interactions, remove those
@@ -625,6 +658,10 @@ impl Driver {
                 winning_solver.name(),
                 winning_settlement
             );
+            if let Some(objective_value) = winning_settlement.objective_value().to_f64() {
+                self.metrics
+                    .settlement_objective_value(objective_value, winning_solver.name());
+            }
 
             winning_settlement.settlement = self
                 .post_processing_pipeline
@@ -647,6 +684,7 @@ impl Driver {
                 )
                 .await
             {
+                settlement_submitted = true;
                 let orders = winning_settlement
                     .settlement
                     .traded_orders()
@@ -680,7 +718,7 @@ impl Driver {
         }
         // Happens after settlement submission so that we do not delay it.
         self.report_simulation_errors(errors, current_block_during_liquidity_fetch, gas_price);
-        Ok(())
+        Ok(settlement_submitted)
     }
 
     fn next_auction_id(&mut self) -> u64 {
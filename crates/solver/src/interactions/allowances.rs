@@ -138,6 +138,10 @@ impl Interaction for Approval {
             }
         }
     }
+
+    fn kind(&self) -> &'static str {
+        "Approval"
+    }
 }
 
 /// An allowance manager that retrive approval interactions for a given owner
@@ -51,6 +51,10 @@ impl Interaction for BalancerSwapGivenOutInteraction {
         let calldata = method.tx.data.expect("no calldata").0;
         vec![(self.vault.address(), 0.into(), Bytes(calldata))]
     }
+
+    fn kind(&self) -> &'static str {
+        "BalancerSwapGivenOutInteraction"
+    }
 }
 
 #[cfg(test)]
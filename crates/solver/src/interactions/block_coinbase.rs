@@ -22,4 +22,8 @@ impl Interaction for PayBlockCoinbase {
             ethcontract::Bytes(METHOD_ID.to_vec()),
         )]
     }
+
+    fn kind(&self) -> &'static str {
+        "PayBlockCoinbase"
+    }
 }
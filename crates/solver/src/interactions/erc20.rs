@@ -24,6 +24,10 @@ impl Interaction for Erc20ApproveInteraction {
     fn encode(&self) -> Vec<EncodedInteraction> {
         vec![self.as_encoded()]
     }
+
+    fn kind(&self) -> &'static str {
+        "Erc20ApproveInteraction"
+    }
 }
 
 #[cfg(test)]
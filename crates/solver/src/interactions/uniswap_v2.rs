@@ -17,6 +17,10 @@ impl Interaction for UniswapInteraction {
     fn encode(&self) -> Vec<EncodedInteraction> {
         vec![self.encode_swap()]
     }
+
+    fn kind(&self) -> &'static str {
+        "UniswapInteraction"
+    }
 }
 
 impl UniswapInteraction {
@@ -35,6 +35,10 @@ impl Interaction for UnwrapWethInteraction {
         let calldata = method.tx.data.expect("no calldata").0;
         vec![(self.weth.address(), 0.into(), Bytes(calldata))]
     }
+
+    fn kind(&self) -> &'static str {
+        "UnwrapWethInteraction"
+    }
 }
 
 #[cfg(test)]
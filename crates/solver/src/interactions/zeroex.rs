@@ -38,4 +38,8 @@ impl Interaction for ZeroExInteraction {
         let calldata = method.tx.data.expect("no calldata").0;
         vec![(self.zeroex.address(), 0.into(), Bytes(calldata))]
     }
+
+    fn kind(&self) -> &'static str {
+        "ZeroExInteraction"
+    }
 }
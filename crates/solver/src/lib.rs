@@ -1,3 +1,4 @@
+pub mod account;
 mod analytics;
 mod auction_preprocessing;
 pub mod driver;
@@ -1,14 +1,14 @@
 use anyhow::anyhow;
 use clap::{ArgEnum, Parser};
 use contracts::{BalancerV2Vault, IUniswapLikeRouter, WETH9};
-use ethcontract::{Account, PrivateKey, H160};
+use ethcontract::{Account, PrivateKey, H160, U256};
 use num::rational::Ratio;
 use reqwest::Url;
 use shared::{
     baseline_solver::BaseTokens,
     current_block::current_block_stream,
     maintenance::{Maintaining, ServiceMaintenance},
-    metrics::{serve_metrics, setup_metrics_registry},
+    metrics::{push_metrics_to_statsd, serve_metrics, setup_metrics_registry},
     network::network_name,
     recent_block_cache::CacheConfig,
     sources::{
@@ -41,9 +41,9 @@ use solver::{
         },
         SolutionSubmitter, StrategyArgs, TransactionStrategy,
     },
-    solver::SolverType,
+    solver::{FeePolicy, SolverDecorators, SolverType},
 };
-use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+use std::{collections::HashMap, path::Path, str::FromStr, sync::Arc, time::Duration};
 
 #[derive(Debug, Parser)]
 struct Arguments {
@@ -71,8 +71,10 @@ struct Arguments {
     balancer_sor_url: Url,
 
     /// The account used by the driver to sign transactions. This can be either
-    /// a 32-byte private key for offline signing, or a 20-byte Ethereum address
-    /// for signing with a local node account.
+    /// a 32-byte private key for offline signing, a 20-byte Ethereum address
+    /// for signing with a local node account, or `keystore:<path>` to load the
+    /// private key from an encrypted JSON keystore whose passphrase is read from
+    /// the `SOLVER_KEYSTORE_PASSPHRASE` environment variable.
     #[clap(long, env, hide_env_values = true)]
     solver_account: Option<SolverAccountArg>,
 
@@ -110,6 +112,12 @@ struct Arguments {
     )]
     solvers: Vec<SolverType>,
 
+    /// Which of the configured solver types are allowed to run. Solvers in `--solvers` whose type
+    /// is not listed here are not started. All configured solvers run if not specified. This
+    /// allows individual solvers to be toggled without changing the solver and account lists.
+    #[clap(long, env, arg_enum, ignore_case = true, use_value_delimiter = true)]
+    enabled_solvers: Option<Vec<SolverType>>,
+
     /// Individual accounts for each solver. See `--solver-account` for more
     /// information about configuring accounts.
     #[clap(
@@ -136,6 +144,45 @@ struct Arguments {
     #[clap(long, env, default_value = "9587")]
     metrics_port: u16,
 
+    /// Tokens for which settled trades are counted per token pair. Only trades where both the
+    /// sell and buy token are listed are counted to limit the number of metric labels.
+    #[clap(long, env, use_value_delimiter = true)]
+    metrics_traded_token_allowlist: Vec<H160>,
+
+    /// The `host:port` address of a StatsD server to which metrics are pushed in addition to being
+    /// served for Prometheus. Metrics are not pushed if not specified.
+    #[clap(long, env)]
+    statsd_address: Option<String>,
+
+    /// How often in seconds metrics are pushed to the StatsD server.
+    #[clap(
+        long,
+        env,
+        default_value = "10",
+        parse(try_from_str = shared::arguments::duration_from_seconds),
+    )]
+    statsd_push_interval: Duration,
+
+    /// The maximum time in seconds between the completion of two run loops. If exceeded the
+    /// service is reported as unhealthy.
+    #[clap(
+        long,
+        env,
+        default_value = "420",
+        parse(try_from_str = shared::arguments::duration_from_seconds),
+    )]
+    max_runloop_duration: Duration,
+
+    /// The time in seconds after startup during which the solver is always considered alive, so
+    /// that a slow first run loop does not cause restarts.
+    #[clap(
+        long,
+        env,
+        default_value = "0",
+        parse(try_from_str = shared::arguments::duration_from_seconds),
+    )]
+    startup_grace_period: Duration,
+
     /// The port at which we serve our metrics
     #[clap(long, env, default_value = "5")]
     max_merged_settlements: usize,
@@ -175,6 +222,11 @@ struct Arguments {
     #[clap(long, env, default_value = "10")]
     zeroex_slippage_bps: u32,
 
+    /// The maximum number of orders a single order solver (like 1Inch or Paraswap) tries to
+    /// settle at the same time.
+    #[clap(long, env, default_value = "1")]
+    single_order_solver_max_concurrency: usize,
+
     /// How to to submit settlement transactions.
     /// Expected to contain either:
     /// 1. One value equal to TransactionStrategyArg::DryRun or
@@ -298,9 +350,36 @@ struct Arguments {
     #[clap(long, env, use_value_delimiter = true)]
     token_list_restriction_for_price_checks: Option<Vec<H160>>,
 
+    /// The protocol fee in basis points of the traded volume that solvers should account for. No
+    /// protocol fee is charged if this is not set.
+    #[clap(long, env)]
+    protocol_fee_bps: Option<u32>,
+
+    /// The maximum number of liquidity entries that get passed to the solvers. Liquidity trading
+    /// tokens of the orders is preferred, the rest is kept by reserve size. Unlimited if not set.
+    #[clap(long, env)]
+    max_liquidity: Option<usize>,
+
     /// How pending transactions should be fetched.
     #[clap(long, env, arg_enum, default_value = "ignore")]
     pending_transaction_config: PendingTransactionConfig,
+
+    /// If set, solvers only get to see orders and liquidity trading these tokens.
+    #[clap(long, env, use_value_delimiter = true)]
+    solver_token_allowlist: Option<Vec<H160>>,
+
+    /// If set, orders whose sell and buy amounts are both worth less than this amount of native
+    /// token (in wei) are not passed to the solvers.
+    #[clap(long, env, parse(try_from_str = U256::from_dec_str))]
+    solver_min_order_volume: Option<U256>,
+
+    /// If set, solvers that take longer than this many seconds return no settlements.
+    #[clap(
+        long,
+        env,
+        parse(try_from_str = shared::arguments::duration_from_seconds),
+    )]
+    solver_timeout: Option<Duration>,
 }
 
 #[derive(Copy, Clone, Debug, clap::ArgEnum)]
@@ -328,10 +407,24 @@ impl SolverAccountArg {
     }
 }
 
+/// Solver accounts starting with this prefix are loaded from the encrypted JSON keystore at the
+/// path following the prefix.
+const KEYSTORE_PREFIX: &str = "keystore:";
+
+/// The environment variable containing the passphrase for solver account keystores.
+const KEYSTORE_PASSPHRASE_ENV: &str = "SOLVER_KEYSTORE_PASSPHRASE";
+
 impl FromStr for SolverAccountArg {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix(KEYSTORE_PREFIX) {
+            return solver::account::private_key_from_keystore(
+                Path::new(path),
+                KEYSTORE_PASSPHRASE_ENV,
+            )
+            .map(SolverAccountArg::PrivateKey);
+        }
         s.parse::<PrivateKey>()
             .map(SolverAccountArg::PrivateKey)
             .or_else(|pk_err| {
@@ -353,12 +446,24 @@ async fn main() {
         args.shared.log_filter.as_str(),
         args.shared.log_stderr_threshold,
     );
+    args.shared.validate();
     tracing::info!("running solver with validated {:#?}", args);
 
     setup_metrics_registry(Some("gp_v2_solver".into()), None);
-    let metrics = Arc::new(Metrics::new().expect("Couldn't register metrics"));
+    let metrics = Arc::new(
+        Metrics::new(
+            args.metrics_traded_token_allowlist
+                .iter()
+                .copied()
+                .collect(),
+            args.max_runloop_duration,
+            args.startup_grace_period,
+        )
+        .expect("Couldn't register metrics"),
+    );
 
-    let client = shared::http_client(args.shared.http_timeout);
+    let client = shared::http_client(&args.shared);
+    let http_retry_policy = shared::http_retry_policy(&args.shared);
 
     let transport = create_instrumented_transport(
         HttpTransport::new(client.clone(), args.shared.node_url, "base".to_string()),
@@ -417,9 +522,10 @@ async fn main() {
         max_retries: args.shared.pool_cache_maximum_retries,
         delay_between_retries: args.shared.pool_cache_delay_between_retries_seconds,
     };
-    let baseline_sources = args.shared.baseline_sources.unwrap_or_else(|| {
-        sources::defaults_for_chain(chain_id).expect("failed to get default baseline sources")
-    });
+    let baseline_sources = args
+        .shared
+        .baseline_sources_for_chain(chain_id)
+        .expect("failed to get baseline sources");
     tracing::info!(?baseline_sources, "using baseline sources");
     let pool_caches: HashMap<BaselineSource, Arc<PoolCache>> =
         sources::uniswap_like_liquidity_sources(&web3, &baseline_sources)
@@ -502,6 +608,11 @@ async fn main() {
             panic!("either SOLVER_ACCOUNTS or SOLVER_ACCOUNT must be set")
         }
     };
+    let solvers = match &args.enabled_solvers {
+        Some(enabled_solvers) => filter_enabled_solvers(solvers, enabled_solvers)
+            .expect("invalid enabled solvers configuration"),
+        None => solvers,
+    };
 
     let zeroex_api = Arc::new(
         DefaultZeroExApi::new(
@@ -512,7 +623,8 @@ async fn main() {
             args.shared.zeroex_api_key,
             client.clone(),
         )
-        .unwrap(),
+        .unwrap()
+        .with_retry_policy(http_retry_policy),
     );
 
     let solver = solver::solver::create(
@@ -534,12 +646,22 @@ async fn main() {
         args.shared.disabled_paraswap_dexs,
         args.shared.paraswap_partner,
         client.clone(),
+        http_retry_policy,
         metrics.clone(),
         zeroex_api.clone(),
         args.zeroex_slippage_bps,
         args.shared.quasimodo_uses_internal_buffers,
         args.shared.mip_uses_internal_buffers,
         args.shared.one_inch_url,
+        args.single_order_solver_max_concurrency,
+        SolverDecorators {
+            token_allowlist: args
+                .solver_token_allowlist
+                .map(|tokens| tokens.into_iter().collect()),
+            min_order_volume: args.solver_min_order_volume,
+            timeout: args.solver_timeout,
+            ..Default::default()
+        },
     )
     .expect("failure creating solvers");
 
@@ -688,6 +810,8 @@ async fn main() {
             .map(|max_price_deviation| Ratio::from_float(max_price_deviation).unwrap()),
         args.token_list_restriction_for_price_checks.into(),
         tenderly,
+        FeePolicy::from_volume_bps(args.protocol_fee_bps),
+        args.max_liquidity,
     );
 
     let maintainer = ServiceMaintenance {
@@ -700,6 +824,9 @@ async fn main() {
     tokio::task::spawn(maintainer.run_maintenance_on_new_block(current_block_stream));
 
     serve_metrics(metrics, ([0, 0, 0, 0], args.metrics_port).into());
+    if let Some(statsd_address) = args.statsd_address {
+        push_metrics_to_statsd(statsd_address, args.statsd_push_interval);
+    }
     driver.run_forever().await;
 }
 
@@ -746,6 +873,27 @@ async fn build_amm_artifacts(
     res
 }
 
+/// Removes all solvers whose type is not enabled. Errors if no solvers would remain.
+fn filter_enabled_solvers<T>(
+    solvers: Vec<(T, SolverType)>,
+    enabled_solvers: &[SolverType],
+) -> anyhow::Result<Vec<(T, SolverType)>> {
+    anyhow::ensure!(
+        !enabled_solvers.is_empty(),
+        "at least one solver type must be enabled"
+    );
+    let solvers = solvers
+        .into_iter()
+        .filter(|(_, solver_type)| enabled_solvers.contains(solver_type))
+        .collect::<Vec<_>>();
+    anyhow::ensure!(
+        !solvers.is_empty(),
+        "none of the configured solvers is enabled by {:?}",
+        enabled_solvers
+    );
+    Ok(solvers)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -785,4 +933,19 @@ mod tests {
             .is_err());
         assert!("not an account".parse::<SolverAccountArg>().is_err());
     }
+
+    #[test]
+    fn filters_enabled_solvers() {
+        let solvers = vec![
+            (0, SolverType::Naive),
+            (1, SolverType::Baseline),
+            (2, SolverType::Naive),
+        ];
+        assert_eq!(
+            filter_enabled_solvers(solvers.clone(), &[SolverType::Naive]).unwrap(),
+            vec![(0, SolverType::Naive), (2, SolverType::Naive)]
+        );
+        assert!(filter_enabled_solvers(solvers.clone(), &[]).is_err());
+        assert!(filter_enabled_solvers(solvers, &[SolverType::Mip]).is_err());
+    }
 }
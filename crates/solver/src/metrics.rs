@@ -3,10 +3,11 @@ use crate::{
     settlement::Revertable,
 };
 use anyhow::Result;
-use ethcontract::U256;
-use model::order::Order;
+use ethcontract::{H160, U256};
+use model::order::{Order, OrderKind};
 use prometheus::{
-    Gauge, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts,
+    Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+    IntGaugeVec, Opts, Registry,
 };
 use shared::{
     metrics::get_metrics_registry,
@@ -18,16 +19,15 @@ use shared::{
     transport::instrumented::TransportMetrics,
 };
 use std::{
+    collections::{HashMap, HashSet},
     convert::TryInto,
     sync::Mutex,
     time::{Duration, Instant},
 };
 use strum::VariantNames;
 
-/// The maximum time between the completion of two run loops. If exceeded the service will be considered unhealthy.
-const MAX_RUNLOOP_DURATION: Duration = Duration::from_secs(7 * 60);
-
 /// The outcome of a solver run.
+#[derive(Clone, Copy)]
 pub enum SolverRunOutcome {
     /// Computed a non-trivial settlement.
     Success,
@@ -41,6 +41,7 @@ pub enum SolverRunOutcome {
 }
 
 /// The outcome of settlement submission.
+#[derive(Clone, Copy)]
 pub enum SettlementSubmissionOutcome {
     /// A settlement transaction was mined and included on the blockchain.
     Success,
@@ -61,6 +62,9 @@ pub enum SettlementSubmissionOutcome {
 pub trait SolverMetrics: Send + Sync {
     fn orders_fetched(&self, orders: &[LimitOrder]);
     fn liquidity_fetched(&self, liquidity: &[Liquidity]);
+    /// Reports the number of liquidity entries that were not passed to the solvers because of the
+    /// liquidity limit.
+    fn liquidity_dropped(&self, count: usize);
     fn settlement_computed(&self, solver_type: &str, start: Instant);
     fn order_settled(&self, order: &Order, solver: &'static str);
     fn settlement_simulation_succeeded(&self, solver: &'static str);
@@ -68,45 +72,98 @@ pub trait SolverMetrics: Send + Sync {
     fn solver_run(&self, outcome: SolverRunOutcome, solver: &'static str);
     fn single_order_solver_succeeded(&self, solver: &'static str);
     fn single_order_solver_failed(&self, solver: &'static str);
+    /// Reports the number of orders a single order solver tried to settle in an auction.
+    fn single_order_solver_attempted(&self, solver: &'static str, count: usize);
     fn settlement_simulation_failed(&self, solver: &'static str);
     fn settlement_submitted(&self, outcome: SettlementSubmissionOutcome, solver: &'static str);
-    fn settlement_access_list_saved_gas(&self, gas_saved: f64, sign: &'static str);
+    /// Reports the gas saved by submitting with an access list. Negative values mean that using
+    /// the access list cost more gas.
+    fn settlement_access_list_saved_gas(&self, gas_saved: f64);
     fn settlement_revertable_status(&self, status: Revertable, solver: &'static str);
-    fn orders_matched_but_not_settled(&self, count: usize);
+    /// Reports the kinds of the orders that were matched by a solver but not settled by the
+    /// winning settlement.
+    fn orders_matched_but_not_settled(&self, kinds: &[OrderKind]);
     fn report_order_surplus(&self, surplus_diff: f64);
-    fn runloop_completed(&self);
+    /// Signals that a runloop completed and whether it submitted a settlement.
+    fn runloop_completed(&self, settlement_submitted: bool);
     fn complete_runloop_until_transaction(&self, duration: Duration);
     fn transaction_submission(&self, duration: Duration);
     fn transaction_gas_price(&self, gas_price: U256);
+    fn solver_timed_out(&self, solver: &'static str);
+    fn solver_retried(&self, solver: &'static str);
+    fn solver_cache_hit(&self, solver: &'static str);
+    fn interaction_used(&self, kind: &str, solver: &'static str);
+    fn settlement_objective_value(&self, value: f64, solver: &'static str);
+    fn token_pair_traded(&self, sell_token: H160, buy_token: H160);
+    fn settlement_gas_used(&self, gas: U256, solver: &'static str);
 }
 
-// TODO add labeled interaction counter once we support more than one interaction
 pub struct Metrics {
     trade_counter: IntCounterVec,
     order_settlement_time: IntCounter,
+    order_settlement_time_histogram: Histogram,
     solver_computation_time: IntCounterVec,
     liquidity: IntGaugeVec,
+    liquidity_dropped: IntCounter,
     settlement_simulations: IntCounterVec,
     settlement_submissions: IntCounterVec,
     settlement_revertable_status: IntCounterVec,
     settlement_access_list_saved_gas: HistogramVec,
     solver_runs: IntCounterVec,
     single_order_solver_runs: IntCounterVec,
-    matched_but_unsettled_orders: IntCounter,
+    single_order_solver_attempts: IntCounterVec,
+    matched_but_unsettled_orders: IntCounterVec,
     transport_requests: HistogramVec,
     pool_cache_hits: IntCounter,
     pool_cache_misses: IntCounter,
     last_runloop_completed: Mutex<Instant>,
+    runloops: IntCounter,
+    productive_runloops: IntCounter,
+    started: Instant,
+    last_solver_success: Mutex<HashMap<&'static str, Instant>>,
     order_surplus_report: Histogram,
     complete_runloop_until_transaction: Histogram,
     transaction_submission: Histogram,
     transaction_gas_price_gwei: Gauge,
+    solver_timeouts: IntCounterVec,
+    solver_retries: IntCounterVec,
+    solver_cache_hits: IntCounterVec,
+    interactions: IntCounterVec,
+    settlement_objective_value: GaugeVec,
+    token_pair_trades: IntCounterVec,
+    traded_token_allowlist: HashSet<H160>,
+    settlement_gas_used: HistogramVec,
+    transaction_gas_price_gwei_histogram: Histogram,
+    /// The maximum time between the completion of two run loops. If exceeded the service will be
+    /// considered unhealthy.
+    max_runloop_duration: Duration,
+    /// The time after startup during which the service is always considered healthy so that a
+    /// slow first run loop does not cause restarts.
+    startup_grace_period: Duration,
 }
 
 impl Metrics {
-    pub fn new() -> Result<Self> {
-        let registry = get_metrics_registry();
+    /// Creates and registers the solver metrics. Trades are only counted per token pair if both
+    /// tokens are part of the traded token allowlist to keep the number of label values bounded.
+    pub fn new(
+        traded_token_allowlist: HashSet<H160>,
+        max_runloop_duration: Duration,
+        startup_grace_period: Duration,
+    ) -> Result<Self> {
+        Self::with_registry(
+            get_metrics_registry(),
+            traded_token_allowlist,
+            max_runloop_duration,
+            startup_grace_period,
+        )
+    }
 
+    fn with_registry(
+        registry: &Registry,
+        traded_token_allowlist: HashSet<H160>,
+        max_runloop_duration: Duration,
+        startup_grace_period: Duration,
+    ) -> Result<Self> {
         let trade_counter = IntCounterVec::new(
             Opts::new("trade_counter", "Number of trades settled"),
             &["solver_type"],
@@ -119,6 +176,17 @@ impl Metrics {
         )?;
         registry.register(Box::new(order_settlement_time.clone()))?;
 
+        let order_settlement_time_histogram = Histogram::with_opts(
+            HistogramOpts::new(
+                "order_settlement_time_seconds_histogram",
+                "Seconds between creation and settlement of an order",
+            )
+            .buckets(vec![
+                1., 5., 15., 30., 60., 120., 300., 600., 1800., 3600., 7200., 21600., 86400.,
+            ]),
+        )?;
+        registry.register(Box::new(order_settlement_time_histogram.clone()))?;
+
         let solver_computation_time = IntCounterVec::new(
             Opts::new(
                 "computation_time_ms",
@@ -179,9 +247,21 @@ impl Metrics {
         )?;
         registry.register(Box::new(single_order_solver_runs.clone()))?;
 
-        let matched_but_unsettled_orders = IntCounter::new(
-            "orders_matched_not_settled",
-            "Counter for the number of orders for which at least one solver computed an execution which was not chosen in this run-loop",
+        let single_order_solver_attempts = IntCounterVec::new(
+            Opts::new(
+                "single_order_solver_attempts",
+                "Number of orders single order solvers tried to settle",
+            ),
+            &["solver_type"],
+        )?;
+        registry.register(Box::new(single_order_solver_attempts.clone()))?;
+
+        let matched_but_unsettled_orders = IntCounterVec::new(
+            Opts::new(
+                "orders_matched_not_settled",
+                "Counter for the number of orders for which at least one solver computed an execution which was not chosen in this run-loop",
+            ),
+            &["kind"],
         )?;
         registry.register(Box::new(matched_but_unsettled_orders.clone()))?;
 
@@ -201,25 +281,43 @@ impl Metrics {
         let transport_requests = HistogramVec::new(opts, &["method"]).unwrap();
         registry.register(Box::new(transport_requests.clone()))?;
 
+        let liquidity_dropped = IntCounter::new(
+            "liquidity_dropped",
+            "Number of liquidity entries dropped because of the liquidity limit.",
+        )?;
+        registry.register(Box::new(liquidity_dropped.clone()))?;
+
         let pool_cache_hits = IntCounter::new(
             "pool_cache_hits",
             "Number of cache hits in the pool fetcher cache.",
         )?;
         registry.register(Box::new(pool_cache_hits.clone()))?;
 
+        let runloops = IntCounter::new("runloops", "Number of completed runloops.")?;
+        registry.register(Box::new(runloops.clone()))?;
+
+        let productive_runloops = IntCounter::new(
+            "productive_runloops",
+            "Number of completed runloops that submitted a settlement.",
+        )?;
+        registry.register(Box::new(productive_runloops.clone()))?;
+
         let pool_cache_misses = IntCounter::new(
             "pool_cache_misses",
             "Number of cache misses in the pool fetcher cache.",
         )?;
         registry.register(Box::new(pool_cache_misses.clone()))?;
 
+        // Exponential buckets from 0.5 seconds to a bit over 4 minutes.
+        let duration_buckets = prometheus::exponential_buckets(0.5, 2., 10)?;
+
         let opts = prometheus::opts!(
             "complete_runloop_until_transaction_seconds",
             "Time a runloop that wants to submit a solution takes until the transaction submission starts."
         );
         let complete_runloop_until_transaction = Histogram::with_opts(HistogramOpts {
             common_opts: opts,
-            buckets: vec![f64::INFINITY],
+            buckets: duration_buckets.clone(),
         })?;
         registry.register(Box::new(complete_runloop_until_transaction.clone()))?;
 
@@ -229,7 +327,7 @@ impl Metrics {
         );
         let transaction_submission = Histogram::with_opts(HistogramOpts {
             common_opts: opts,
-            buckets: vec![f64::INFINITY],
+            buckets: duration_buckets,
         })?;
         registry.register(Box::new(transaction_submission.clone()))?;
 
@@ -240,28 +338,138 @@ impl Metrics {
         let transaction_gas_price_gwei = Gauge::with_opts(opts).unwrap();
         registry.register(Box::new(transaction_gas_price_gwei.clone()))?;
 
+        let transaction_gas_price_gwei_histogram = Histogram::with_opts(
+            HistogramOpts::new(
+                "transaction_gas_price_gwei_histogram",
+                "Distribution of gas prices used by settlement transactions.",
+            )
+            .buckets(vec![
+                1., 2., 5., 10., 20., 30., 50., 75., 100., 150., 200., 300., 500., 1000.,
+            ]),
+        )?;
+        registry.register(Box::new(transaction_gas_price_gwei_histogram.clone()))?;
+
+        let solver_timeouts = IntCounterVec::new(
+            Opts::new(
+                "solver_timeouts",
+                "Number of times a solver was cancelled because it exceeded its time limit",
+            ),
+            &["solver_type"],
+        )?;
+        registry.register(Box::new(solver_timeouts.clone()))?;
+
+        let solver_retries = IntCounterVec::new(
+            Opts::new(
+                "solver_retries",
+                "Number of times a solver was run again after returning an error",
+            ),
+            &["solver_type"],
+        )?;
+        registry.register(Box::new(solver_retries.clone()))?;
+
+        let solver_cache_hits = IntCounterVec::new(
+            Opts::new(
+                "solver_cache_hits",
+                "Number of times a cached solution was reused for an identical auction",
+            ),
+            &["solver_type"],
+        )?;
+        registry.register(Box::new(solver_cache_hits.clone()))?;
+
+        let interactions = IntCounterVec::new(
+            Opts::new(
+                "interactions",
+                "Number of interactions in submitted settlements by kind",
+            ),
+            &["kind", "solver_type"],
+        )?;
+        registry.register(Box::new(interactions.clone()))?;
+
+        let settlement_objective_value = GaugeVec::new(
+            Opts::new(
+                "settlement_objective_value",
+                "Objective value of the winning settlement",
+            ),
+            &["solver_type"],
+        )?;
+        registry.register(Box::new(settlement_objective_value.clone()))?;
+
+        let token_pair_trades = IntCounterVec::new(
+            Opts::new(
+                "token_pair_trades",
+                "Number of trades settled between allowlisted tokens",
+            ),
+            &["sell_token", "buy_token"],
+        )?;
+        registry.register(Box::new(token_pair_trades.clone()))?;
+
+        let settlement_gas_used = HistogramVec::new(
+            HistogramOpts::new(
+                "settlement_gas_used",
+                "Gas used by mined settlement transactions",
+            )
+            .buckets(vec![
+                50_000., 100_000., 150_000., 200_000., 300_000., 400_000., 500_000., 750_000.,
+                1_000_000., 1_500_000., 2_000_000., 3_000_000.,
+            ]),
+            &["solver_type"],
+        )?;
+        registry.register(Box::new(settlement_gas_used.clone()))?;
+
         Ok(Self {
             trade_counter,
             order_settlement_time,
+            order_settlement_time_histogram,
             solver_computation_time,
             liquidity,
+            liquidity_dropped,
             settlement_simulations,
             settlement_submissions,
             settlement_revertable_status,
             solver_runs,
             single_order_solver_runs,
+            single_order_solver_attempts,
             matched_but_unsettled_orders,
             transport_requests,
             pool_cache_hits,
             pool_cache_misses,
             last_runloop_completed: Mutex::new(Instant::now()),
+            runloops,
+            productive_runloops,
+            started: Instant::now(),
+            last_solver_success: Default::default(),
             order_surplus_report,
             complete_runloop_until_transaction,
             transaction_submission,
             transaction_gas_price_gwei,
             settlement_access_list_saved_gas,
+            solver_timeouts,
+            solver_retries,
+            solver_cache_hits,
+            interactions,
+            settlement_objective_value,
+            token_pair_trades,
+            traded_token_allowlist,
+            settlement_gas_used,
+            transaction_gas_price_gwei_histogram,
+            max_runloop_duration,
+            startup_grace_period,
         })
     }
+
+    /// Returns whether the specified solver computed a non-trivial settlement within the maximum
+    /// run loop duration. Solvers that haven't succeeded yet are considered alive until the
+    /// maximum run loop duration has passed since the metrics were created.
+    pub fn is_solver_alive(&self, solver: &str) -> bool {
+        let last_success = self
+            .last_solver_success
+            .lock()
+            .expect("thread holding mutex panicked")
+            .get(solver)
+            .copied()
+            .unwrap_or(self.started);
+        Instant::now().duration_since(last_success) <= self.max_runloop_duration
+    }
 }
 
 impl SolverMetrics for Metrics {
@@ -291,6 +499,10 @@ impl SolverMetrics for Metrics {
         })
     }
 
+    fn liquidity_dropped(&self, count: usize) {
+        self.liquidity_dropped.inc_by(count as u64);
+    }
+
     fn settlement_computed(&self, solver_type: &str, start: Instant) {
         self.solver_computation_time
             .with_label_values(&[solver_type])
@@ -307,12 +519,15 @@ impl SolverMetrics for Metrics {
         let time_to_settlement =
             chrono::offset::Utc::now().signed_duration_since(order.metadata.creation_date);
         self.trade_counter.with_label_values(&[solver]).inc();
+        self.token_pair_traded(order.creation.sell_token, order.creation.buy_token);
         self.order_settlement_time.inc_by(
             time_to_settlement
                 .num_seconds()
                 .try_into()
                 .unwrap_or_default(),
-        )
+        );
+        self.order_settlement_time_histogram
+            .observe(time_to_settlement.num_milliseconds().max(0) as f64 / 1000.);
     }
 
     fn settlement_simulation_succeeded(&self, solver: &'static str) {
@@ -329,7 +544,13 @@ impl SolverMetrics for Metrics {
 
     fn solver_run(&self, outcome: SolverRunOutcome, solver: &'static str) {
         let result = match outcome {
-            SolverRunOutcome::Success => "success",
+            SolverRunOutcome::Success => {
+                self.last_solver_success
+                    .lock()
+                    .expect("thread holding mutex panicked")
+                    .insert(solver, Instant::now());
+                "success"
+            }
             SolverRunOutcome::Empty => "empty",
             SolverRunOutcome::Timeout => "timeout",
             SolverRunOutcome::Failure => "failure",
@@ -349,6 +570,12 @@ impl SolverMetrics for Metrics {
             .inc()
     }
 
+    fn single_order_solver_attempted(&self, solver: &'static str, count: usize) {
+        self.single_order_solver_attempts
+            .with_label_values(&[solver])
+            .inc_by(count as u64)
+    }
+
     fn settlement_simulation_failed(&self, solver: &'static str) {
         self.settlement_simulations
             .with_label_values(&["failure", solver])
@@ -370,21 +597,34 @@ impl SolverMetrics for Metrics {
             .inc()
     }
 
-    fn settlement_access_list_saved_gas(&self, gas_saved: f64, label: &'static str) {
+    fn settlement_access_list_saved_gas(&self, gas_saved: f64) {
+        let sign = if gas_saved.is_sign_positive() {
+            "positive"
+        } else {
+            "negative"
+        };
         self.settlement_access_list_saved_gas
-            .with_label_values(&[label])
-            .observe(gas_saved);
+            .with_label_values(&[sign])
+            .observe(gas_saved.abs());
     }
 
-    fn orders_matched_but_not_settled(&self, count: usize) {
-        self.matched_but_unsettled_orders.inc_by(count as u64);
+    fn orders_matched_but_not_settled(&self, kinds: &[OrderKind]) {
+        for kind in kinds {
+            self.matched_but_unsettled_orders
+                .with_label_values(&[kind.label()])
+                .inc();
+        }
     }
 
     fn report_order_surplus(&self, surplus_diff: f64) {
         self.order_surplus_report.observe(surplus_diff)
     }
 
-    fn runloop_completed(&self) {
+    fn runloop_completed(&self, settlement_submitted: bool) {
+        self.runloops.inc();
+        if settlement_submitted {
+            self.productive_runloops.inc();
+        }
         *self
             .last_runloop_completed
             .lock()
@@ -401,8 +641,9 @@ impl SolverMetrics for Metrics {
     }
 
     fn transaction_gas_price(&self, gas_price: U256) {
-        self.transaction_gas_price_gwei
-            .set(gas_price.to_f64_lossy() / 1e9)
+        let gwei = gas_price.to_f64_lossy() / 1e9;
+        self.transaction_gas_price_gwei.set(gwei);
+        self.transaction_gas_price_gwei_histogram.observe(gwei);
     }
 
     fn settlement_revertable_status(&self, status: Revertable, solver: &'static str) {
@@ -414,6 +655,45 @@ impl SolverMetrics for Metrics {
             .with_label_values(&[result, solver])
             .inc()
     }
+
+    fn solver_timed_out(&self, solver: &'static str) {
+        self.solver_timeouts.with_label_values(&[solver]).inc()
+    }
+
+    fn solver_retried(&self, solver: &'static str) {
+        self.solver_retries.with_label_values(&[solver]).inc()
+    }
+
+    fn solver_cache_hit(&self, solver: &'static str) {
+        self.solver_cache_hits.with_label_values(&[solver]).inc()
+    }
+
+    fn interaction_used(&self, kind: &str, solver: &'static str) {
+        self.interactions.with_label_values(&[kind, solver]).inc()
+    }
+
+    fn settlement_objective_value(&self, value: f64, solver: &'static str) {
+        self.settlement_objective_value
+            .with_label_values(&[solver])
+            .set(value)
+    }
+
+    fn token_pair_traded(&self, sell_token: H160, buy_token: H160) {
+        if !self.traded_token_allowlist.contains(&sell_token)
+            || !self.traded_token_allowlist.contains(&buy_token)
+        {
+            return;
+        }
+        self.token_pair_trades
+            .with_label_values(&[&format!("{:?}", sell_token), &format!("{:?}", buy_token)])
+            .inc()
+    }
+
+    fn settlement_gas_used(&self, gas: U256, solver: &'static str) {
+        self.settlement_gas_used
+            .with_label_values(&[solver])
+            .observe(gas.to_f64_lossy())
+    }
 }
 
 impl TransportMetrics for Metrics {
@@ -443,12 +723,15 @@ impl BalancerPoolCacheMetrics for Metrics {
 #[async_trait::async_trait]
 impl LivenessChecking for Metrics {
     async fn is_alive(&self) -> bool {
+        if self.started.elapsed() < self.startup_grace_period {
+            return true;
+        }
         Instant::now().duration_since(
             *self
                 .last_runloop_completed
                 .lock()
                 .expect("thread holding mutex panicked"),
-        ) <= MAX_RUNLOOP_DURATION
+        ) <= self.max_runloop_duration
     }
 }
 
@@ -458,6 +741,7 @@ pub struct NoopMetrics {}
 impl SolverMetrics for NoopMetrics {
     fn orders_fetched(&self, _liquidity: &[LimitOrder]) {}
     fn liquidity_fetched(&self, _liquidity: &[Liquidity]) {}
+    fn liquidity_dropped(&self, _: usize) {}
     fn settlement_computed(&self, _solver_type: &str, _start: Instant) {}
     fn order_settled(&self, _: &Order, _: &'static str) {}
     fn settlement_simulation_succeeded(&self, _: &'static str) {}
@@ -465,16 +749,24 @@ impl SolverMetrics for NoopMetrics {
     fn solver_run(&self, _: SolverRunOutcome, _: &'static str) {}
     fn single_order_solver_succeeded(&self, _: &'static str) {}
     fn single_order_solver_failed(&self, _: &'static str) {}
+    fn single_order_solver_attempted(&self, _: &'static str, _: usize) {}
     fn settlement_simulation_failed(&self, _: &'static str) {}
     fn settlement_submitted(&self, _: SettlementSubmissionOutcome, _: &'static str) {}
     fn settlement_revertable_status(&self, _: Revertable, _: &'static str) {}
-    fn settlement_access_list_saved_gas(&self, _: f64, _: &'static str) {}
-    fn orders_matched_but_not_settled(&self, _: usize) {}
+    fn settlement_access_list_saved_gas(&self, _: f64) {}
+    fn orders_matched_but_not_settled(&self, _: &[OrderKind]) {}
     fn report_order_surplus(&self, _: f64) {}
-    fn runloop_completed(&self) {}
+    fn runloop_completed(&self, _: bool) {}
     fn complete_runloop_until_transaction(&self, _: Duration) {}
     fn transaction_submission(&self, _: Duration) {}
     fn transaction_gas_price(&self, _: U256) {}
+    fn solver_timed_out(&self, _: &'static str) {}
+    fn solver_retried(&self, _: &'static str) {}
+    fn solver_cache_hit(&self, _: &'static str) {}
+    fn interaction_used(&self, _: &str, _: &'static str) {}
+    fn settlement_objective_value(&self, _: f64, _: &'static str) {}
+    fn token_pair_traded(&self, _: H160, _: H160) {}
+    fn settlement_gas_used(&self, _: U256, _: &'static str) {}
 }
 
 #[cfg(test)]
@@ -483,12 +775,122 @@ mod tests {
 
     #[test]
     fn metrics_work() {
-        let metrics = Metrics::new().unwrap();
+        let metrics = Metrics::new(
+            Default::default(),
+            Duration::from_secs(7 * 60),
+            Duration::ZERO,
+        )
+        .unwrap();
         metrics.settlement_computed("asdf", Instant::now());
         metrics.order_settled(&Default::default(), "test");
         metrics.settlement_simulation_succeeded("test");
         metrics.settlement_simulation_failed("test");
         metrics.settlement_submitted(SettlementSubmissionOutcome::Success, "test");
-        metrics.orders_matched_but_not_settled(20);
+        metrics.orders_matched_but_not_settled(&[OrderKind::Buy, OrderKind::Sell]);
+        metrics.solver_run(SolverRunOutcome::Success, "test");
+        assert!(metrics.is_solver_alive("test"));
+        assert!(metrics.is_solver_alive("not_run_yet"));
+    }
+
+    #[test]
+    fn access_list_saved_gas_is_split_by_sign() {
+        let metrics = Metrics::with_registry(
+            &Registry::new(),
+            Default::default(),
+            Duration::from_secs(1),
+            Duration::ZERO,
+        )
+        .unwrap();
+        metrics.settlement_access_list_saved_gas(100.);
+        metrics.settlement_access_list_saved_gas(-30.);
+        metrics.settlement_access_list_saved_gas(-20.);
+
+        let histogram = |sign| {
+            metrics
+                .settlement_access_list_saved_gas
+                .with_label_values(&[sign])
+        };
+        assert_eq!(histogram("positive").get_sample_count(), 1);
+        assert_eq!(histogram("positive").get_sample_sum(), 100.);
+        assert_eq!(histogram("negative").get_sample_count(), 2);
+        assert_eq!(histogram("negative").get_sample_sum(), 50.);
+    }
+
+    #[tokio::test]
+    async fn liveness_uses_configured_max_runloop_duration() {
+        let metrics = Metrics::with_registry(
+            &Registry::new(),
+            Default::default(),
+            Duration::from_millis(100),
+            Duration::ZERO,
+        )
+        .unwrap();
+        metrics.runloop_completed(false);
+        assert!(metrics.is_alive().await);
+
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!metrics.is_alive().await);
+
+        metrics.runloop_completed(false);
+        assert!(metrics.is_alive().await);
+    }
+
+    #[tokio::test]
+    async fn liveness_is_true_during_startup_grace_period() {
+        let metrics = Metrics::with_registry(
+            &Registry::new(),
+            Default::default(),
+            Duration::ZERO,
+            Duration::from_secs(3600),
+        )
+        .unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(metrics.is_alive().await);
+
+        let metrics = Metrics::with_registry(
+            &Registry::new(),
+            Default::default(),
+            Duration::ZERO,
+            Duration::ZERO,
+        )
+        .unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(!metrics.is_alive().await);
+    }
+
+    #[test]
+    fn counts_single_order_solver_attempts() {
+        let metrics = Metrics::with_registry(
+            &Registry::new(),
+            Default::default(),
+            Duration::from_secs(60),
+            Duration::ZERO,
+        )
+        .unwrap();
+        metrics.single_order_solver_attempted("solver", 3);
+        metrics.single_order_solver_attempted("solver", 2);
+        assert_eq!(
+            metrics
+                .single_order_solver_attempts
+                .with_label_values(&["solver"])
+                .get(),
+            5
+        );
+    }
+
+    #[test]
+    fn counts_productive_runloops() {
+        let metrics = Metrics::with_registry(
+            &Registry::new(),
+            Default::default(),
+            Duration::from_secs(60),
+            Duration::ZERO,
+        )
+        .unwrap();
+        metrics.runloop_completed(false);
+        metrics.runloop_completed(true);
+        metrics.runloop_completed(false);
+        assert_eq!(metrics.runloops.get(), 3);
+        assert_eq!(metrics.productive_runloops.get(), 1);
     }
 }
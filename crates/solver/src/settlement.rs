@@ -175,12 +175,19 @@ pub trait Interaction: std::fmt::Debug + Send + Sync {
     // never fail. Then the question becomes whether interactions should be allowed to fail encoding
     // for other reasons.
     fn encode(&self) -> Vec<EncodedInteraction>;
+
+    /// Short name of the kind of interaction used for metrics.
+    fn kind(&self) -> &'static str;
 }
 
 impl Interaction for EncodedInteraction {
     fn encode(&self) -> Vec<EncodedInteraction> {
         vec![self.clone()]
     }
+
+    fn kind(&self) -> &'static str {
+        "EncodedInteraction"
+    }
 }
 
 #[cfg(test)]
@@ -192,6 +199,10 @@ impl Interaction for NoopInteraction {
     fn encode(&self) -> Vec<EncodedInteraction> {
         Vec::new()
     }
+
+    fn kind(&self) -> &'static str {
+        "NoopInteraction"
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -199,6 +210,7 @@ pub struct Settlement {
     pub encoder: SettlementEncoder,
 }
 
+#[derive(Clone, Copy)]
 pub enum Revertable {
     NoRisk,
     HighRisk,
@@ -428,6 +440,15 @@ impl Settlement {
         Ok(Self { encoder: merged })
     }
 
+    /// Combines two independent settlements into one without consuming them.
+    ///
+    /// Clearing prices of the settlements get scaled to be consistent with each other and
+    /// interactions of both settlements are kept. Returns an error if both settlements trade the
+    /// same order or if their clearing prices can't be made consistent.
+    pub fn try_merge(&self, other: &Settlement) -> Result<Settlement> {
+        self.clone().merge(other.clone())
+    }
+
     // Calculates the risk level for settlement to be reverted
     pub fn revertable(&self) -> Revertable {
         if self.encoder.execution_plan().is_empty() {
@@ -514,7 +535,7 @@ pub mod tests {
     use super::*;
     use crate::{liquidity::SettlementHandling, settlement::external_prices::externalprices};
     use maplit::hashmap;
-    use model::order::{OrderCreation, OrderKind};
+    use model::order::{OrderBuilder, OrderCreation, OrderKind};
     use num::FromPrimitive;
     use shared::addr;
 
@@ -1403,4 +1424,65 @@ pub mod tests {
         // to the user!
         assert!(objective_value(&amm, 657196) > objective_value(&pmm, 405053));
     }
+
+    fn settlement_with_order(uid: u8, sell_token: H160, buy_token: H160) -> Settlement {
+        let mut settlement = Settlement::new(hashmap! {
+            sell_token => 1.into(),
+            buy_token => 1.into(),
+        });
+        let mut order = OrderBuilder::default()
+            .with_sell_token(sell_token)
+            .with_sell_amount(10.into())
+            .with_buy_token(buy_token)
+            .with_buy_amount(10.into())
+            .build();
+        order.metadata.uid.0[0] = uid;
+        settlement
+            .encoder
+            .add_trade(order, 10.into(), 0.into())
+            .unwrap();
+        settlement.encoder.append_to_execution_plan(NoopInteraction);
+        settlement
+    }
+
+    #[test]
+    fn try_merge_combines_independent_settlements() {
+        let token0 = H160::from_low_u64_be(0);
+        let token1 = H160::from_low_u64_be(1);
+        let token2 = H160::from_low_u64_be(2);
+        let settlement0 = settlement_with_order(0, token0, token1);
+        let settlement1 = settlement_with_order(1, token1, token2);
+
+        let merged = settlement0.try_merge(&settlement1).unwrap();
+        assert_eq!(merged.traded_orders().count(), 2);
+        assert_eq!(merged.encoder.execution_plan().len(), 2);
+        assert_eq!(
+            merged.clearing_prices(),
+            &hashmap! {
+                token0 => 1.into(),
+                token1 => 1.into(),
+                token2 => 1.into(),
+            }
+        );
+        // The merged settlements are left untouched.
+        assert_eq!(settlement0.traded_orders().count(), 1);
+        assert_eq!(settlement1.traded_orders().count(), 1);
+    }
+
+    #[test]
+    fn try_merge_rejects_settlements_trading_the_same_order() {
+        let token0 = H160::from_low_u64_be(0);
+        let token1 = H160::from_low_u64_be(1);
+        let settlement0 = settlement_with_order(0, token0, token1);
+        let settlement1 = settlement_with_order(0, token0, token1);
+
+        assert!(settlement0.try_merge(&settlement1).is_err());
+    }
+
+    #[test]
+    fn interactions_report_their_kind() {
+        assert_eq!(NoopInteraction.kind(), "NoopInteraction");
+        let interaction: EncodedInteraction = Default::default();
+        assert_eq!(interaction.kind(), "EncodedInteraction");
+    }
 }
@@ -8,10 +8,14 @@
 use anyhow::{bail, Result};
 use ethcontract::{H160, U256};
 use lazy_static::lazy_static;
-use model::order::BUY_ETH_ADDRESS;
+use model::{auction::NATIVE_PRICE_SCALE, order::BUY_ETH_ADDRESS};
 use num::{BigInt, BigRational, One as _, ToPrimitive as _};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use shared::conversions::U256Ext as _;
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
 
 /// A collection of external prices used for converting token amounts to native
 /// assets.
@@ -63,6 +67,11 @@ impl ExternalPrices {
         self.0.get(token)
     }
 
+    /// Returns an iterator over all tokens and their prices in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&H160, &BigRational)> + '_ {
+        self.0.iter()
+    }
+
     /// Converts a token amount into its native asset equivalent.
     ///
     /// # Panic
@@ -98,6 +107,38 @@ impl ExternalPrices {
     }
 }
 
+/// External prices are serialized as a map from token to exchange rate where the exchange rates
+/// are formatted as exact `numerator/denominator` strings.
+impl Serialize for ExternalPrices {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0
+            .iter()
+            .map(|(token, price)| (*token, price.to_string()))
+            .collect::<HashMap<_, _>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExternalPrices {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let prices = HashMap::<H160, String>::deserialize(deserializer)?;
+        prices
+            .into_iter()
+            .map(|(token, price)| {
+                let price = BigRational::from_str(&price).map_err(de::Error::custom)?;
+                Ok((token, price))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
 impl Default for ExternalPrices {
     fn default() -> Self {
         Self::new(Default::default(), Default::default()).unwrap()
@@ -105,7 +146,7 @@ impl Default for ExternalPrices {
 }
 
 lazy_static! {
-    static ref UNIT: BigInt = BigInt::from(1_000_000_000_000_000_000_u128);
+    static ref UNIT: BigInt = BigInt::from(NATIVE_PRICE_SCALE);
 }
 
 /// Converts a token price from the orderbook API `/auction` endpoint to an
@@ -183,6 +224,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialization_roundtrip() {
+        let native_token = H160([42; 20]);
+        let prices = ExternalPrices::new(
+            native_token,
+            hashmap! {
+                H160([1; 20]) => BigRational::new(1.into(), 3.into()),
+            },
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&prices).unwrap();
+        let deserialized: ExternalPrices = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.0, prices.0);
+    }
+
     #[test]
     fn from_auction_price_errors_on_invalid_native_prices() {
         let native_token = H160([42; 20]);
@@ -1,8 +1,9 @@
 use crate::interactions::allowances::AllowanceManager;
-use crate::metrics::SolverMetrics;
+use crate::metrics::{SolverMetrics, SolverRunOutcome};
 use crate::settlement::external_prices::ExternalPrices;
 use crate::solver::balancer_sor_solver::BalancerSorSolver;
 use crate::{
+    auction_preprocessing,
     liquidity::{LimitOrder, Liquidity},
     settlement::Settlement,
 };
@@ -10,14 +11,16 @@ use anyhow::{anyhow, Result};
 use baseline_solver::BaselineSolver;
 use contracts::{BalancerV2Vault, GPv2Settlement};
 use ethcontract::errors::ExecutionError;
-use ethcontract::{Account, H160, U256};
+use ethcontract::{Account, H160, H256, U256};
 use http_solver::{buffers::BufferRetriever, HttpSolver};
 use naive_solver::NaiveSolver;
-use num::BigRational;
+use num::{BigRational, ToPrimitive as _};
 use oneinch_solver::OneInchSolver;
 use paraswap_solver::ParaswapSolver;
+use rand::{rngs::StdRng, seq::SliceRandom as _, SeedableRng as _};
 use reqwest::{Client, Url};
 use shared::balancer_sor_api::DefaultBalancerSorApi;
+use shared::http_retry::HttpRetryPolicy;
 use shared::http_solver::{DefaultHttpSolverApi, SolverConfig};
 use shared::zeroex_api::ZeroExApi;
 use shared::{
@@ -25,10 +28,11 @@ use shared::{
 };
 use single_order_solver::SingleOrderSolver;
 use std::{
-    sync::Arc,
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
-use web3::types::AccessList;
+use web3::{signing, types::AccessList};
 use zeroex_solver::ZeroExSolver;
 
 pub mod balancer_sor_solver;
@@ -37,6 +41,7 @@ pub mod http_solver;
 mod naive_solver;
 mod oneinch_solver;
 mod paraswap_solver;
+pub mod recording_solver;
 mod single_order_solver;
 pub mod uni_v3_router_solver;
 mod zeroex_solver;
@@ -63,6 +68,12 @@ pub trait Solver: Send + Sync + 'static {
     ///
     /// This method is used for logging and metrics collection.
     fn name(&self) -> &'static str;
+
+    /// Returns the priority of the solver. Settlements of solvers with a higher priority are
+    /// preferred when they are otherwise equally good.
+    fn priority(&self) -> i32 {
+        0
+    }
 }
 
 /// A batch auction for a solver to produce a settlement for.
@@ -82,7 +93,7 @@ pub struct Auction {
     /// settling orders.
     pub liquidity: Vec<Liquidity>,
 
-    /// The current gas price estimate.
+    /// The current gas price estimate in wei per unit of gas (not gwei).
     pub gas_price: f64,
 
     /// The deadline for computing a solution.
@@ -101,6 +112,38 @@ pub struct Auction {
     /// External prices are garanteed to exist for all orders included in the
     /// current auction.
     pub external_prices: ExternalPrices,
+
+    /// The protocol fee that gets charged on the orders of this auction.
+    ///
+    /// Solvers that don't take the fee into account when computing executable amounts can ignore
+    /// it.
+    pub fee_policy: FeePolicy,
+}
+
+/// The protocol fee charged on orders.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeePolicy {
+    /// No protocol fee is charged.
+    NoFee,
+    /// A fee of the specified number of basis points of the traded volume is charged.
+    Volume { bps: u32 },
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self::NoFee
+    }
+}
+
+impl FeePolicy {
+    /// Returns the volume based fee policy for the specified basis points or no fee if none were
+    /// specified.
+    pub fn from_volume_bps(bps: Option<u32>) -> Self {
+        match bps {
+            Some(bps) => Self::Volume { bps },
+            None => Self::NoFee,
+        }
+    }
 }
 
 impl Default for Auction {
@@ -116,7 +159,78 @@ impl Default for Auction {
             gas_price: Default::default(),
             deadline: never,
             external_prices: Default::default(),
+            fee_policy: Default::default(),
+        }
+    }
+}
+
+impl Auction {
+    /// Returns a hash of the contents of the auction: its orders and their amounts, liquidity, gas
+    /// price, external prices and fee policy. Unlike `id` it does not depend on the driver instance so the same auction has the
+    /// same fingerprint across restarts. The order of orders and liquidity does not matter.
+    pub fn fingerprint(&self) -> H256 {
+        self.fingerprint_with_gas_price(self.gas_price)
+    }
+
+    /// Returns the fingerprint the auction would have with the specified gas price.
+    fn fingerprint_with_gas_price(&self, gas_price: f64) -> H256 {
+        // Partially fillable orders keep their id while their executable amounts change.
+        let mut orders = self
+            .orders
+            .iter()
+            .map(|order| {
+                let mut bytes = Vec::new();
+                append_length_prefixed(&mut bytes, order.id.as_bytes());
+                bytes.extend_from_slice(&u256_bytes(order.sell_amount));
+                bytes.extend_from_slice(&u256_bytes(order.buy_amount));
+                bytes.extend_from_slice(&u256_bytes(order.unscaled_subsidized_fee));
+                bytes.extend_from_slice(&u256_bytes(order.scaled_unsubsidized_fee));
+                bytes
+            })
+            .collect::<Vec<_>>();
+        orders.sort_unstable();
+        let mut liquidity = self
+            .liquidity
+            .iter()
+            .map(liquidity_bytes)
+            .collect::<Vec<_>>();
+        liquidity.sort_unstable();
+        let mut prices = self.external_prices.iter().collect::<Vec<_>>();
+        prices.sort_unstable_by_key(|(token, _)| *token);
+
+        // Variable length fields are prefixed with their length so that different auctions can't
+        // serialize to the same bytes.
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(orders.len() as u64).to_be_bytes());
+        for order in &orders {
+            append_length_prefixed(&mut bytes, order);
+        }
+        bytes.extend_from_slice(&(liquidity.len() as u64).to_be_bytes());
+        for liquidity in &liquidity {
+            append_length_prefixed(&mut bytes, liquidity);
+        }
+        bytes.extend_from_slice(&gas_price.to_bits().to_be_bytes());
+        for (token, price) in prices {
+            bytes.extend_from_slice(token.as_bytes());
+            append_length_prefixed(&mut bytes, &price.numer().to_signed_bytes_be());
+            append_length_prefixed(&mut bytes, &price.denom().to_signed_bytes_be());
+        }
+        match self.fee_policy {
+            FeePolicy::NoFee => bytes.push(0),
+            FeePolicy::Volume { bps } => {
+                bytes.push(1);
+                bytes.extend_from_slice(&bps.to_be_bytes());
+            }
         }
+        H256(signing::keccak256(&bytes))
+    }
+
+    /// Removes all liquidity that does not trade any of the tokens that appear in the auction's
+    /// orders. This makes the auction smaller at the cost of multi-hop routes through unrelated
+    /// tokens.
+    pub fn restrict_liquidity_to_order_tokens(&mut self) {
+        self.liquidity
+            .retain(auction_preprocessing::trades_order_token(&self.orders));
     }
 }
 
@@ -133,7 +247,7 @@ pub type SettlementWithError = (
     ExecutionError,
 );
 
-#[derive(Copy, Clone, Debug, clap::ArgEnum)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ArgEnum)]
 #[clap(rename_all = "verbatim")]
 pub enum SolverType {
     Naive,
@@ -167,12 +281,15 @@ pub fn create(
     disabled_paraswap_dexs: Vec<String>,
     paraswap_partner: Option<String>,
     client: Client,
+    http_retry_policy: HttpRetryPolicy,
     solver_metrics: Arc<dyn SolverMetrics>,
     zeroex_api: Arc<dyn ZeroExApi>,
     zeroex_slippage_bps: u32,
     quasimodo_uses_internal_buffers: bool,
     mip_uses_internal_buffers: bool,
     one_inch_url: Url,
+    single_order_solver_max_concurrency: usize,
+    decorators: SolverDecorators,
 ) -> Result<Solvers> {
     // Tiny helper function to help out with type inference. Otherwise, all
     // `Box::new(...)` expressions would have to be cast `as Box<dyn Solver>`.
@@ -200,6 +317,7 @@ pub fn create(
                     chain_id,
                     base: url,
                     client: client.clone(),
+                    retry_policy: http_retry_policy,
                     config,
                 },
                 account,
@@ -258,9 +376,11 @@ pub fn create(
                         chain_id,
                         disabled_one_inch_protocols.clone(),
                         client.clone(),
+                        http_retry_policy,
                         one_inch_url.clone(),
                     )?,
                     solver_metrics.clone(),
+                    single_order_solver_max_concurrency,
                 )),
                 SolverType::ZeroEx => {
                     let zeroex_solver = ZeroExSolver::new(
@@ -275,6 +395,7 @@ pub fn create(
                     shared(SingleOrderSolver::new(
                         zeroex_solver,
                         solver_metrics.clone(),
+                        single_order_solver_max_concurrency,
                     ))
                 }
                 SolverType::Paraswap => shared(SingleOrderSolver::new(
@@ -286,9 +407,11 @@ pub fn create(
                         paraswap_slippage_bps,
                         disabled_paraswap_dexs.clone(),
                         client.clone(),
+                        http_retry_policy,
                         paraswap_partner.clone(),
                     ),
                     solver_metrics.clone(),
+                    single_order_solver_max_concurrency,
                 )),
                 SolverType::BalancerSor => shared(SingleOrderSolver::new(
                     BalancerSorSolver::new(
@@ -299,16 +422,21 @@ pub fn create(
                             })?
                             .clone(),
                         settlement_contract.clone(),
-                        Arc::new(DefaultBalancerSorApi::new(
-                            client.clone(),
-                            balancer_sor_url.clone(),
-                            chain_id,
-                        )?),
+                        Arc::new(
+                            DefaultBalancerSorApi::new(
+                                client.clone(),
+                                balancer_sor_url.clone(),
+                                chain_id,
+                            )?
+                            .with_retry_policy(http_retry_policy),
+                        ),
                         allowance_mananger.clone(),
                     ),
                     solver_metrics.clone(),
+                    single_order_solver_max_concurrency,
                 )),
             };
+            let solver = solver.map(|solver| decorators.apply(solver, solver_metrics.clone()));
 
             if let Ok(solver) = &solver {
                 tracing::info!(
@@ -322,6 +450,71 @@ pub fn create(
         .collect()
 }
 
+/// Wrappers that get applied to every solver created by `create`. The filters are applied first
+/// so that the timeout also covers them.
+#[derive(Clone, Debug, Default)]
+pub struct SolverDecorators {
+    /// Only orders and liquidity trading these tokens are passed to the solvers.
+    pub token_allowlist: Option<HashSet<H160>>,
+    /// Orders whose native value is below this amount are removed before solving.
+    pub min_order_volume: Option<U256>,
+    /// Which legs of an order need to meet `min_order_volume`.
+    pub volume_filter_mode: VolumeFilterMode,
+    /// Solvers that take longer than this return no settlements.
+    pub timeout: Option<Duration>,
+}
+
+impl SolverDecorators {
+    pub fn apply(
+        &self,
+        solver: Arc<dyn Solver>,
+        metrics: Arc<dyn SolverMetrics>,
+    ) -> Arc<dyn Solver> {
+        let mut solver = solver;
+        if let Some(allowlist) = &self.token_allowlist {
+            solver = Arc::new(AllowlistFilteringSolver::new(
+                Box::new(solver),
+                allowlist.clone(),
+            ));
+        }
+        if let Some(min_value) = self.min_order_volume {
+            solver = Arc::new(SellVolumeFilteringSolver::new(
+                Box::new(solver),
+                min_value,
+                self.volume_filter_mode,
+            ));
+        }
+        if let Some(timeout) = self.timeout {
+            solver = Arc::new(TimeoutSolver::new(
+                solver,
+                timeout,
+                TimeoutBehavior::Empty,
+                metrics,
+            ));
+        }
+        solver
+    }
+}
+
+#[async_trait::async_trait]
+impl Solver for Arc<dyn Solver> {
+    async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+        self.as_ref().solve(auction).await
+    }
+
+    fn account(&self) -> &Account {
+        self.as_ref().account()
+    }
+
+    fn name(&self) -> &'static str {
+        self.as_ref().name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.as_ref().priority()
+    }
+}
+
 /// Returns a naive solver to be used e.g. in e2e tests.
 pub fn naive_solver(account: Account) -> Arc<dyn Solver> {
     Arc::new(NaiveSolver::new(account))
@@ -332,13 +525,34 @@ pub fn naive_solver(account: Account) -> Arc<dyn Solver> {
 pub struct SellVolumeFilteringSolver {
     inner: Box<dyn Solver + Send + Sync>,
     min_value: BigRational,
+    mode: VolumeFilterMode,
+}
+
+/// Which legs of an order need to meet the minimum volume for the order to be kept.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VolumeFilterMode {
+    /// Either the buy or the sell amount.
+    Either,
+    /// Both the buy and the sell amount.
+    Both,
+}
+
+impl Default for VolumeFilterMode {
+    fn default() -> Self {
+        Self::Either
+    }
 }
 
 impl SellVolumeFilteringSolver {
-    pub fn new(inner: Box<dyn Solver + Send + Sync>, min_value: U256) -> Self {
+    pub fn new(
+        inner: Box<dyn Solver + Send + Sync>,
+        min_value: U256,
+        mode: VolumeFilterMode,
+    ) -> Self {
         Self {
             inner,
             min_value: min_value.to_big_rational(),
+            mode,
         }
     }
 
@@ -348,13 +562,22 @@ impl SellVolumeFilteringSolver {
         mut orders: Vec<LimitOrder>,
         external_prices: &ExternalPrices,
     ) -> Vec<LimitOrder> {
-        let is_minimum_volume = |token: &H160, amount: &U256| {
-            let native_amount = external_prices.get_native_amount(*token, amount.to_big_rational());
-            native_amount >= self.min_value
+        let is_minimum_volume = |token: &H160, amount: &U256| match external_prices
+            .try_get_native_amount(*token, amount.to_big_rational())
+        {
+            Some(native_amount) => native_amount >= self.min_value,
+            None => {
+                tracing::warn!(?token, "missing native price for volume filtering");
+                false
+            }
         };
         orders.retain(|order| {
-            is_minimum_volume(&order.buy_token, &order.buy_amount)
-                || is_minimum_volume(&order.sell_token, &order.sell_amount)
+            let buy = || is_minimum_volume(&order.buy_token, &order.buy_amount);
+            let sell = || is_minimum_volume(&order.sell_token, &order.sell_amount);
+            match self.mode {
+                VolumeFilterMode::Either => buy() || sell(),
+                VolumeFilterMode::Both => buy() && sell(),
+            }
         });
         orders
     }
@@ -381,99 +604,1268 @@ impl Solver for SellVolumeFilteringSolver {
     fn name(&self) -> &'static str {
         self.inner.name()
     }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+}
+
+/// A solver that removes all orders and liquidity trading tokens that are not part of an allowlist
+/// and passes the remaining auction onto an inner solver implementation.
+pub struct AllowlistFilteringSolver {
+    inner: Box<dyn Solver + Send + Sync>,
+    allowlist: HashSet<H160>,
+}
+
+impl AllowlistFilteringSolver {
+    pub fn new(inner: Box<dyn Solver + Send + Sync>, allowlist: HashSet<H160>) -> Self {
+        Self { inner, allowlist }
+    }
+
+    fn filter_auction(&self, auction: &mut Auction) {
+        auction.orders.retain(|order| {
+            self.allowlist.contains(&order.sell_token) && self.allowlist.contains(&order.buy_token)
+        });
+        auction.liquidity.retain(|liquidity| {
+            liquidity.all_token_pairs().iter().all(|pair| {
+                let (token0, token1) = pair.get();
+                self.allowlist.contains(&token0) && self.allowlist.contains(&token1)
+            })
+        });
+    }
 }
 
-#[cfg(test)]
-struct DummySolver;
-#[cfg(test)]
 #[async_trait::async_trait]
-impl Solver for DummySolver {
-    async fn solve(&self, _: Auction) -> Result<Vec<Settlement>> {
-        todo!()
+impl Solver for AllowlistFilteringSolver {
+    async fn solve(&self, mut auction: Auction) -> Result<Vec<Settlement>> {
+        let (orders, liquidity) = (auction.orders.len(), auction.liquidity.len());
+        self.filter_auction(&mut auction);
+        tracing::debug!(
+            "Filtered {} orders and {} liquidity because of tokens not in the allowlist",
+            orders - auction.orders.len(),
+            liquidity - auction.liquidity.len()
+        );
+        self.inner.solve(auction).await
     }
-    fn account(&self) -> &ethcontract::Account {
-        todo!()
+
+    fn account(&self) -> &Account {
+        self.inner.account()
     }
+
     fn name(&self) -> &'static str {
-        "DummySolver"
+        self.inner.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
     }
 }
-#[cfg(test)]
-pub fn dummy_arc_solver() -> Arc<dyn Solver> {
-    Arc::new(DummySolver)
+
+/// What a `TimeoutSolver` returns when the inner solver does not finish in time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TimeoutBehavior {
+    /// Return an empty list of settlements.
+    Empty,
+    /// Return an error.
+    Error,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{liquidity::LimitOrder, settlement::external_prices::externalprices};
-    use model::order::OrderKind;
-    use num::One as _;
+/// A solver that cancels the inner solver if it takes longer than the configured timeout.
+pub struct TimeoutSolver {
+    inner: Arc<dyn Solver>,
+    timeout: Duration,
+    behavior: TimeoutBehavior,
+    metrics: Arc<dyn SolverMetrics>,
+}
 
-    /// Dummy solver returning no settlements
-    pub struct NoopSolver();
-    #[async_trait::async_trait]
-    impl Solver for NoopSolver {
-        async fn solve(&self, _: Auction) -> Result<Vec<Settlement>> {
-            Ok(Vec::new())
+impl TimeoutSolver {
+    pub fn new(
+        inner: Arc<dyn Solver>,
+        timeout: Duration,
+        behavior: TimeoutBehavior,
+        metrics: Arc<dyn SolverMetrics>,
+    ) -> Self {
+        Self {
+            inner,
+            timeout,
+            behavior,
+            metrics,
         }
+    }
+}
 
-        fn account(&self) -> &Account {
-            unimplemented!()
+#[async_trait::async_trait]
+impl Solver for TimeoutSolver {
+    async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+        match tokio::time::timeout(self.timeout, self.inner.solve(auction)).await {
+            Ok(result) => result,
+            Err(_) => {
+                tracing::warn!(
+                    "solver {} timed out after {:?}",
+                    self.inner.name(),
+                    self.timeout
+                );
+                self.metrics.solver_timed_out(self.inner.name());
+                match self.behavior {
+                    TimeoutBehavior::Empty => Ok(Vec::new()),
+                    TimeoutBehavior::Error => Err(anyhow!(
+                        "solver {} timed out after {:?}",
+                        self.inner.name(),
+                        self.timeout
+                    )),
+                }
+            }
         }
+    }
 
-        fn name(&self) -> &'static str {
-            "NoopSolver"
+    fn account(&self) -> &Account {
+        self.inner.account()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+}
+
+/// A solver that runs the inner solver and reports the objective value of its settlements but
+/// never returns any of them, so that a new solver can run alongside the real ones without its
+/// settlements being submitted. The reported objective value does not account for gas costs as
+/// the settlements are not simulated.
+pub struct DryRunSolver {
+    inner: Arc<dyn Solver>,
+    metrics: Arc<dyn SolverMetrics>,
+}
+
+impl DryRunSolver {
+    pub fn new(inner: Arc<dyn Solver>, metrics: Arc<dyn SolverMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+#[async_trait::async_trait]
+impl Solver for DryRunSolver {
+    async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+        let external_prices = auction.external_prices.clone();
+        let settlements = self.inner.solve(auction).await?;
+        for settlement in settlements {
+            let objective_value = settlement.total_surplus(&external_prices)
+                + settlement.total_scaled_unsubsidized_fees(&external_prices);
+            let objective_value = objective_value.to_f64();
+            tracing::info!(
+                solver = self.inner.name(),
+                ?objective_value,
+                ?settlement,
+                "dry run settlement"
+            );
+            if let Some(objective_value) = objective_value {
+                self.metrics
+                    .settlement_objective_value(objective_value, self.inner.name());
+            }
         }
+        Ok(Vec::new())
     }
 
-    #[tokio::test]
-    async fn test_filtering_solver_removes_limit_orders_with_too_little_volume() {
-        let sell_token = H160::from_low_u64_be(1);
-        let buy_token = H160::from_low_u64_be(2);
-        let orders = vec![
-            // Orders with high enough amount
-            LimitOrder {
-                sell_amount: 100_000.into(),
-                sell_token,
-                buy_token,
-                kind: OrderKind::Sell,
-                ..Default::default()
-            },
-            LimitOrder {
-                sell_amount: 500_000.into(),
-                sell_token,
-                buy_token,
-                kind: OrderKind::Sell,
-                ..Default::default()
-            },
-            // Order with small amount
-            LimitOrder {
-                sell_amount: 100.into(),
-                sell_token,
-                buy_token,
-                kind: OrderKind::Sell,
-                ..Default::default()
-            },
-        ];
+    fn account(&self) -> &Account {
+        self.inner.account()
+    }
 
-        let solver = SellVolumeFilteringSolver::new(Box::new(NoopSolver()), 50_000.into());
-        let prices = externalprices! { native_token: sell_token, buy_token => BigRational::one() };
-        assert_eq!(solver.filter_orders(orders, &prices).await.len(), 2);
+    fn name(&self) -> &'static str {
+        self.inner.name()
     }
 
-    #[tokio::test]
-    #[should_panic]
-    async fn test_filtering_solver_panics_orders_without_price_estimate() {
-        let sell_token = H160::from_low_u64_be(1);
-        let orders = vec![LimitOrder {
-            sell_amount: 100_000.into(),
-            sell_token,
-            ..Default::default()
-        }];
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+}
 
-        let prices = Default::default();
-        let solver = SellVolumeFilteringSolver::new(Box::new(NoopSolver()), 0.into());
-        assert_eq!(solver.filter_orders(orders, &prices).await.len(), 0);
+/// A solver that runs multiple inner solvers concurrently under a single name and returns all of
+/// their settlements. Inner solvers that fail or don't finish before the auction deadline are
+/// skipped so that the results of the others are still returned.
+pub struct CombinedSolver {
+    name: &'static str,
+    solvers: Vec<Arc<dyn Solver>>,
+}
+
+impl CombinedSolver {
+    /// Creates a new combined solver. There has to be at least one inner solver whose account is
+    /// used for the combined solver.
+    pub fn new(name: &'static str, solvers: Vec<Arc<dyn Solver>>) -> Self {
+        assert!(!solvers.is_empty(), "combined solver without inner solvers");
+        Self { name, solvers }
+    }
+}
+
+#[async_trait::async_trait]
+impl Solver for CombinedSolver {
+    async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+        let deadline = auction.deadline;
+        let results = futures::future::join_all(self.solvers.iter().map(|solver| {
+            let auction = auction.clone();
+            async move {
+                let result = tokio::time::timeout_at(deadline.into(), solver.solve(auction)).await;
+                (solver.name(), result)
+            }
+        }))
+        .await;
+
+        let mut settlements = Vec::new();
+        let mut failures = 0;
+        for (name, result) in results {
+            match result {
+                Ok(Ok(solver_settlements)) => settlements.extend(solver_settlements),
+                Ok(Err(err)) => {
+                    tracing::warn!(?err, "inner solver {} of {} failed", name, self.name);
+                    failures += 1;
+                }
+                Err(_) => {
+                    tracing::warn!("inner solver {} of {} timed out", name, self.name);
+                    failures += 1;
+                }
+            }
+        }
+        if failures == self.solvers.len() {
+            return Err(anyhow!("all inner solvers of {} failed", self.name));
+        }
+        Ok(settlements)
+    }
+
+    fn account(&self) -> &Account {
+        self.solvers[0].account()
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The settlements of all inner solvers are returned under the combined name so they share the
+    /// highest priority of the inner solvers.
+    fn priority(&self) -> i32 {
+        self.solvers
+            .iter()
+            .map(|solver| solver.priority())
+            .max()
+            .expect("combined solver without inner solvers")
+    }
+}
+
+/// A solver that skips auctions with more orders than the inner solver can handle and returns no
+/// settlements for them instead.
+pub struct MaxOrdersSolver {
+    inner: Arc<dyn Solver>,
+    max_orders: usize,
+    metrics: Arc<dyn SolverMetrics>,
+}
+
+impl MaxOrdersSolver {
+    pub fn new(inner: Arc<dyn Solver>, max_orders: usize, metrics: Arc<dyn SolverMetrics>) -> Self {
+        Self {
+            inner,
+            max_orders,
+            metrics,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Solver for MaxOrdersSolver {
+    async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+        if auction.orders.len() > self.max_orders {
+            tracing::debug!(
+                "skipping auction with {} orders for solver {} which supports at most {}",
+                auction.orders.len(),
+                self.inner.name(),
+                self.max_orders
+            );
+            self.metrics
+                .solver_run(SolverRunOutcome::Empty, self.inner.name());
+            return Ok(Vec::new());
+        }
+        self.inner.solve(auction).await
+    }
+
+    fn account(&self) -> &Account {
+        self.inner.account()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+}
+
+/// A solver that overrides the priority of the inner solver.
+pub struct PrioritizedSolver {
+    inner: Arc<dyn Solver>,
+    priority: i32,
+}
+
+impl PrioritizedSolver {
+    pub fn new(inner: Arc<dyn Solver>, priority: i32) -> Self {
+        Self { inner, priority }
+    }
+}
+
+#[async_trait::async_trait]
+impl Solver for PrioritizedSolver {
+    async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+        self.inner.solve(auction).await
+    }
+
+    fn account(&self) -> &Account {
+        self.inner.account()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+}
+
+/// A solver that multiplies the gas price of the auction by a factor before passing it on to the
+/// inner solver. This makes solvers that under-estimate gas bid more conservatively.
+pub struct GasPriceScalingSolver {
+    inner: Arc<dyn Solver>,
+    factor: f64,
+}
+
+impl GasPriceScalingSolver {
+    pub fn new(inner: Arc<dyn Solver>, factor: f64) -> Self {
+        Self { inner, factor }
+    }
+}
+
+#[async_trait::async_trait]
+impl Solver for GasPriceScalingSolver {
+    async fn solve(&self, mut auction: Auction) -> Result<Vec<Settlement>> {
+        auction.gas_price *= self.factor;
+        self.inner.solve(auction).await
+    }
+
+    fn account(&self) -> &Account {
+        self.inner.account()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+}
+
+/// A solver that runs the inner solver again when it returns an error.
+pub struct RetrySolver {
+    inner: Arc<dyn Solver>,
+    max_retries: usize,
+    backoff: Duration,
+    metrics: Arc<dyn SolverMetrics>,
+}
+
+impl RetrySolver {
+    pub fn new(
+        inner: Arc<dyn Solver>,
+        max_retries: usize,
+        backoff: Duration,
+        metrics: Arc<dyn SolverMetrics>,
+    ) -> Self {
+        Self {
+            inner,
+            max_retries,
+            backoff,
+            metrics,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Solver for RetrySolver {
+    async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+        let mut retries = 0;
+        loop {
+            let err = match self.inner.solve(auction.clone()).await {
+                Ok(settlements) => return Ok(settlements),
+                Err(err) => err,
+            };
+            // Don't retry if the deadline would be exceeded before the next attempt starts.
+            if retries >= self.max_retries || Instant::now() + self.backoff >= auction.deadline {
+                return Err(err);
+            }
+            retries += 1;
+            tracing::warn!(
+                ?err,
+                "solver {} failed, retrying ({}/{})",
+                self.inner.name(),
+                retries,
+                self.max_retries
+            );
+            self.metrics.solver_retried(self.inner.name());
+            tokio::time::sleep(self.backoff).await;
+        }
+    }
+
+    fn account(&self) -> &Account {
+        self.inner.account()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+}
+
+/// A solver that shuffles the orders of the auction before passing it on to the inner solver so
+/// that solvers which depend on the order of the orders don't systematically favor some of them.
+pub struct ShufflingSolver {
+    inner: Arc<dyn Solver>,
+    rng: Mutex<StdRng>,
+}
+
+impl ShufflingSolver {
+    /// Creates a new shuffling solver. If a seed is specified the sequence of shuffles is
+    /// deterministic.
+    pub fn new(inner: Arc<dyn Solver>, seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self {
+            inner,
+            rng: Mutex::new(rng),
+        }
+    }
+
+    fn shuffle(&self, orders: &mut [LimitOrder]) {
+        orders.shuffle(&mut *self.rng.lock().unwrap());
+    }
+}
+
+#[async_trait::async_trait]
+impl Solver for ShufflingSolver {
+    async fn solve(&self, mut auction: Auction) -> Result<Vec<Settlement>> {
+        self.shuffle(&mut auction.orders);
+        self.inner.solve(auction).await
+    }
+
+    fn account(&self) -> &Account {
+        self.inner.account()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+}
+
+/// A solver that reuses the settlements of the inner solver for identical auctions that are solved
+/// again within the configured time to live.
+///
+/// Auctions are considered identical if they have the same fingerprint once their gas prices are
+/// rounded down to the configured bucket size.
+pub struct CachingSolver {
+    inner: Arc<dyn Solver>,
+    ttl: Duration,
+    gas_price_bucket_size: f64,
+    metrics: Arc<dyn SolverMetrics>,
+    cache: Mutex<HashMap<H256, (Instant, Vec<Settlement>)>>,
+}
+
+impl CachingSolver {
+    /// Creates a new caching solver. The gas price bucket size is in wei and must be positive.
+    pub fn new(
+        inner: Arc<dyn Solver>,
+        ttl: Duration,
+        gas_price_bucket_size: f64,
+        metrics: Arc<dyn SolverMetrics>,
+    ) -> Self {
+        assert!(
+            gas_price_bucket_size > 0.,
+            "gas price bucket size must be positive"
+        );
+        Self {
+            inner,
+            ttl,
+            gas_price_bucket_size,
+            metrics,
+            cache: Default::default(),
+        }
+    }
+
+    fn auction_key(&self, auction: &Auction) -> H256 {
+        auction.fingerprint_with_gas_price(
+            (auction.gas_price / self.gas_price_bucket_size).floor() * self.gas_price_bucket_size,
+        )
+    }
+
+    fn cached(&self, key: H256) -> Option<Vec<Settlement>> {
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (solved_at, _)| solved_at.elapsed() < self.ttl);
+        cache.get(&key).map(|(_, settlements)| settlements.clone())
+    }
+}
+
+fn append_length_prefixed(bytes: &mut Vec<u8>, value: &[u8]) {
+    bytes.extend_from_slice(&(value.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(value);
+}
+
+fn u256_bytes(value: U256) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
+/// Serializes the parts of the liquidity that identify it and its state. Every kind of liquidity
+/// starts with a distinct tag byte.
+fn liquidity_bytes(liquidity: &Liquidity) -> Vec<u8> {
+    fn sorted_tokens<T>(reserves: &HashMap<H160, T>) -> Vec<(&H160, &T)> {
+        let mut tokens = reserves.iter().collect::<Vec<_>>();
+        tokens.sort_unstable_by_key(|(token, _)| *token);
+        tokens
+    }
+
+    let mut bytes = Vec::new();
+    match liquidity {
+        Liquidity::ConstantProduct(amm) => {
+            let (token0, token1) = amm.tokens.get();
+            bytes.push(0);
+            bytes.extend_from_slice(token0.as_bytes());
+            bytes.extend_from_slice(token1.as_bytes());
+            bytes.extend_from_slice(&amm.reserves.0.to_be_bytes());
+            bytes.extend_from_slice(&amm.reserves.1.to_be_bytes());
+        }
+        Liquidity::BalancerWeighted(amm) => {
+            bytes.push(1);
+            for (token, state) in sorted_tokens(&amm.reserves) {
+                bytes.extend_from_slice(token.as_bytes());
+                bytes.extend_from_slice(&u256_bytes(state.common.balance));
+                bytes.extend_from_slice(&u256_bytes(state.weight.as_uint256()));
+            }
+        }
+        Liquidity::BalancerStable(amm) => {
+            bytes.push(2);
+            for (token, state) in sorted_tokens(&amm.reserves) {
+                bytes.extend_from_slice(token.as_bytes());
+                bytes.extend_from_slice(&u256_bytes(state.balance));
+            }
+        }
+        Liquidity::LimitOrder(order) => {
+            bytes.push(3);
+            append_length_prefixed(&mut bytes, order.id.as_bytes());
+            bytes.extend_from_slice(&u256_bytes(order.sell_amount));
+            bytes.extend_from_slice(&u256_bytes(order.buy_amount));
+        }
+    }
+    bytes
+}
+
+#[async_trait::async_trait]
+impl Solver for CachingSolver {
+    async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+        let key = self.auction_key(&auction);
+        if let Some(settlements) = self.cached(key) {
+            tracing::debug!("reusing cached settlements of solver {}", self.inner.name());
+            self.metrics.solver_cache_hit(self.inner.name());
+            return Ok(settlements);
+        }
+        let settlements = self.inner.solve(auction).await?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), settlements.clone()));
+        Ok(settlements)
+    }
+
+    fn account(&self) -> &Account {
+        self.inner.account()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+}
+
+#[cfg(test)]
+struct DummySolver;
+#[cfg(test)]
+#[async_trait::async_trait]
+impl Solver for DummySolver {
+    async fn solve(&self, _: Auction) -> Result<Vec<Settlement>> {
+        todo!()
+    }
+    fn account(&self) -> &ethcontract::Account {
+        todo!()
+    }
+    fn name(&self) -> &'static str {
+        "DummySolver"
+    }
+}
+#[cfg(test)]
+pub fn dummy_arc_solver() -> Arc<dyn Solver> {
+    Arc::new(DummySolver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        liquidity::{tests::CapturingSettlementHandler, ConstantProductOrder, LimitOrder},
+        metrics::NoopMetrics,
+        settlement::external_prices::externalprices,
+    };
+    use maplit::hashset;
+    use model::order::OrderKind;
+    use num::One as _;
+
+    /// Dummy solver returning no settlements
+    pub struct NoopSolver();
+    #[async_trait::async_trait]
+    impl Solver for NoopSolver {
+        async fn solve(&self, _: Auction) -> Result<Vec<Settlement>> {
+            Ok(Vec::new())
+        }
+
+        fn account(&self) -> &Account {
+            unimplemented!()
+        }
+
+        fn name(&self) -> &'static str {
+            "NoopSolver"
+        }
+    }
+
+    /// Dummy solver returning the specified number of empty settlements
+    pub struct SettlingSolver(pub usize);
+    #[async_trait::async_trait]
+    impl Solver for SettlingSolver {
+        async fn solve(&self, _: Auction) -> Result<Vec<Settlement>> {
+            Ok(vec![Settlement::default(); self.0])
+        }
+
+        fn account(&self) -> &Account {
+            unimplemented!()
+        }
+
+        fn name(&self) -> &'static str {
+            "SettlingSolver"
+        }
+    }
+
+    /// Dummy solver that takes a long time to return no settlements
+    pub struct SlowSolver();
+    #[async_trait::async_trait]
+    impl Solver for SlowSolver {
+        async fn solve(&self, _: Auction) -> Result<Vec<Settlement>> {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(Vec::new())
+        }
+
+        fn account(&self) -> &Account {
+            unimplemented!()
+        }
+
+        fn name(&self) -> &'static str {
+            "SlowSolver"
+        }
+    }
+
+    #[tokio::test]
+    async fn timeout_solver_returns_early() {
+        let solver = TimeoutSolver::new(
+            Arc::new(SlowSolver()),
+            Duration::from_millis(10),
+            TimeoutBehavior::Empty,
+            Arc::new(NoopMetrics::default()),
+        );
+        let start = Instant::now();
+        let settlements = solver.solve(Auction::default()).await.unwrap();
+        assert!(settlements.is_empty());
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        let solver = TimeoutSolver::new(
+            Arc::new(SlowSolver()),
+            Duration::from_millis(10),
+            TimeoutBehavior::Error,
+            Arc::new(NoopMetrics::default()),
+        );
+        assert!(solver.solve(Auction::default()).await.is_err());
+    }
+
+    /// Dummy solver that fails a number of times before returning no settlements
+    pub struct FailingSolver(std::sync::atomic::AtomicUsize);
+    #[async_trait::async_trait]
+    impl Solver for FailingSolver {
+        async fn solve(&self, _: Auction) -> Result<Vec<Settlement>> {
+            let remaining_failures = &self.0;
+            match remaining_failures.fetch_update(
+                std::sync::atomic::Ordering::SeqCst,
+                std::sync::atomic::Ordering::SeqCst,
+                |failures| failures.checked_sub(1),
+            ) {
+                Ok(_) => Err(anyhow!("failure")),
+                Err(_) => Ok(Vec::new()),
+            }
+        }
+
+        fn account(&self) -> &Account {
+            unimplemented!()
+        }
+
+        fn name(&self) -> &'static str {
+            "FailingSolver"
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_solver_retries_failures() {
+        let solver = RetrySolver::new(
+            Arc::new(FailingSolver(2.into())),
+            2,
+            Duration::from_millis(1),
+            Arc::new(NoopMetrics::default()),
+        );
+        assert!(solver.solve(Auction::default()).await.is_ok());
+
+        let solver = RetrySolver::new(
+            Arc::new(FailingSolver(3.into())),
+            2,
+            Duration::from_millis(1),
+            Arc::new(NoopMetrics::default()),
+        );
+        assert!(solver.solve(Auction::default()).await.is_err());
+
+        let solver = RetrySolver::new(
+            Arc::new(FailingSolver(1.into())),
+            2,
+            Duration::from_millis(1),
+            Arc::new(NoopMetrics::default()),
+        );
+        let auction = Auction {
+            deadline: Instant::now(),
+            ..Default::default()
+        };
+        assert!(solver.solve(auction).await.is_err());
+    }
+
+    /// Dummy solver counting how often it was called
+    #[derive(Default)]
+    pub struct CountingSolver(std::sync::atomic::AtomicUsize);
+    #[async_trait::async_trait]
+    impl Solver for CountingSolver {
+        async fn solve(&self, _: Auction) -> Result<Vec<Settlement>> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(Vec::new())
+        }
+
+        fn account(&self) -> &Account {
+            unimplemented!()
+        }
+
+        fn name(&self) -> &'static str {
+            "CountingSolver"
+        }
+    }
+
+    #[tokio::test]
+    async fn caching_solver_reuses_settlements_for_identical_auctions() {
+        let inner = Arc::new(CountingSolver::default());
+        let solver = CachingSolver::new(
+            inner.clone(),
+            Duration::from_secs(60),
+            1e9,
+            Arc::new(NoopMetrics::default()),
+        );
+        let auction = |id, gas_price| Auction {
+            id,
+            orders: vec![LimitOrder {
+                id: "order".to_string(),
+                ..Default::default()
+            }],
+            gas_price,
+            ..Default::default()
+        };
+        let calls = || inner.0.load(std::sync::atomic::Ordering::SeqCst);
+
+        solver.solve(auction(0, 1.1e9)).await.unwrap();
+        solver.solve(auction(1, 1.2e9)).await.unwrap();
+        assert_eq!(calls(), 1);
+
+        solver.solve(auction(2, 2.1e9)).await.unwrap();
+        assert_eq!(calls(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn caching_solver_rejects_empty_gas_price_buckets() {
+        CachingSolver::new(
+            dummy_arc_solver(),
+            Duration::from_secs(60),
+            0.,
+            Arc::new(NoopMetrics::default()),
+        );
+    }
+
+    #[test]
+    fn auction_carries_fee_policy() {
+        assert_eq!(Auction::default().fee_policy, FeePolicy::NoFee);
+        assert_eq!(FeePolicy::from_volume_bps(None), FeePolicy::NoFee);
+
+        let auction = Auction {
+            fee_policy: FeePolicy::from_volume_bps(Some(5)),
+            ..Default::default()
+        };
+        assert_eq!(auction.fee_policy, FeePolicy::Volume { bps: 5 });
+    }
+
+    #[test]
+    fn allowlist_filtering_solver_removes_disallowed_tokens() {
+        let allowed0 = H160::from_low_u64_be(1);
+        let allowed1 = H160::from_low_u64_be(2);
+        let disallowed = H160::from_low_u64_be(3);
+        let order = |id: &str, sell_token, buy_token| LimitOrder {
+            id: id.to_string(),
+            sell_token,
+            buy_token,
+            ..Default::default()
+        };
+        let amm = |token0, token1| {
+            Liquidity::ConstantProduct(ConstantProductOrder {
+                tokens: model::TokenPair::new(token0, token1).unwrap(),
+                reserves: (1, 1),
+                fee: num::rational::Ratio::new(3, 1000),
+                settlement_handling: CapturingSettlementHandler::arc(),
+            })
+        };
+        let mut auction = Auction {
+            orders: vec![
+                order("allowed", allowed0, allowed1),
+                order("disallowed_sell", disallowed, allowed1),
+                order("disallowed_buy", allowed0, disallowed),
+            ],
+            liquidity: vec![
+                amm(allowed0, allowed1),
+                amm(allowed0, disallowed),
+                Liquidity::LimitOrder(order("disallowed_liquidity", disallowed, allowed0)),
+            ],
+            ..Default::default()
+        };
+
+        let solver =
+            AllowlistFilteringSolver::new(Box::new(NoopSolver()), hashset! { allowed0, allowed1 });
+        solver.filter_auction(&mut auction);
+        assert_eq!(
+            auction
+                .orders
+                .iter()
+                .map(|order| order.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["allowed"]
+        );
+        assert_eq!(auction.liquidity, vec![amm(allowed0, allowed1)]);
+    }
+
+    #[test]
+    fn shuffling_solver_is_deterministic_with_seed() {
+        let orders = (0..10)
+            .map(|i| LimitOrder {
+                id: i.to_string(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let shuffled_ids = |solver: &ShufflingSolver| {
+            let mut orders = orders.clone();
+            solver.shuffle(&mut orders);
+            orders.into_iter().map(|order| order.id).collect::<Vec<_>>()
+        };
+
+        let solver0 = ShufflingSolver::new(Arc::new(NoopSolver()), Some(42));
+        let solver1 = ShufflingSolver::new(Arc::new(NoopSolver()), Some(42));
+        let ids = shuffled_ids(&solver0);
+        assert_eq!(ids, shuffled_ids(&solver1));
+
+        let mut sorted_ids = ids;
+        sorted_ids.sort_by_key(|id| id.parse::<u32>().unwrap());
+        assert_eq!(
+            sorted_ids,
+            (0..10).map(|i| i.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filtering_solver_removes_limit_orders_with_too_little_volume() {
+        let sell_token = H160::from_low_u64_be(1);
+        let buy_token = H160::from_low_u64_be(2);
+        let orders = vec![
+            // Orders with high enough amount
+            LimitOrder {
+                sell_amount: 100_000.into(),
+                sell_token,
+                buy_token,
+                kind: OrderKind::Sell,
+                ..Default::default()
+            },
+            LimitOrder {
+                sell_amount: 500_000.into(),
+                sell_token,
+                buy_token,
+                kind: OrderKind::Sell,
+                ..Default::default()
+            },
+            // Order with small amount
+            LimitOrder {
+                sell_amount: 100.into(),
+                sell_token,
+                buy_token,
+                kind: OrderKind::Sell,
+                ..Default::default()
+            },
+        ];
+
+        let solver = SellVolumeFilteringSolver::new(
+            Box::new(NoopSolver()),
+            50_000.into(),
+            VolumeFilterMode::Either,
+        );
+        let prices = externalprices! { native_token: sell_token, buy_token => BigRational::one() };
+        assert_eq!(solver.filter_orders(orders, &prices).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_filtering_solver_requires_both_volumes() {
+        let sell_token = H160::from_low_u64_be(1);
+        let buy_token = H160::from_low_u64_be(2);
+        let orders = vec![
+            // Both amounts high enough
+            LimitOrder {
+                sell_amount: 100_000.into(),
+                buy_amount: 100_000.into(),
+                sell_token,
+                buy_token,
+                kind: OrderKind::Sell,
+                ..Default::default()
+            },
+            // Only sell amount high enough
+            LimitOrder {
+                sell_amount: 100_000.into(),
+                buy_amount: 100.into(),
+                sell_token,
+                buy_token,
+                kind: OrderKind::Sell,
+                ..Default::default()
+            },
+            // Only buy amount high enough
+            LimitOrder {
+                sell_amount: 100.into(),
+                buy_amount: 100_000.into(),
+                sell_token,
+                buy_token,
+                kind: OrderKind::Sell,
+                ..Default::default()
+            },
+        ];
+
+        let prices = externalprices! { native_token: sell_token, buy_token => BigRational::one() };
+        let solver = SellVolumeFilteringSolver::new(
+            Box::new(NoopSolver()),
+            50_000.into(),
+            VolumeFilterMode::Either,
+        );
+        assert_eq!(solver.filter_orders(orders.clone(), &prices).await.len(), 3);
+
+        let solver = SellVolumeFilteringSolver::new(
+            Box::new(NoopSolver()),
+            50_000.into(),
+            VolumeFilterMode::Both,
+        );
+        let filtered = solver.filter_orders(orders, &prices).await;
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].buy_amount, 100_000.into());
+        assert_eq!(filtered[0].sell_amount, 100_000.into());
+    }
+
+    #[tokio::test]
+    async fn test_filtering_solver_removes_orders_without_price_estimate() {
+        let sell_token = H160::from_low_u64_be(1);
+        let buy_token = H160::from_low_u64_be(2);
+        let orders = vec![LimitOrder {
+            sell_amount: 100_000.into(),
+            sell_token,
+            buy_token,
+            ..Default::default()
+        }];
+
+        let prices = Default::default();
+        let solver = SellVolumeFilteringSolver::new(
+            Box::new(NoopSolver()),
+            0.into(),
+            VolumeFilterMode::Either,
+        );
+        assert_eq!(solver.filter_orders(orders, &prices).await.len(), 0);
+    }
+
+    #[test]
+    fn restricts_liquidity_to_order_tokens() {
+        let token = H160::from_low_u64_be;
+        let pool = |token0, token1| {
+            Liquidity::ConstantProduct(ConstantProductOrder {
+                tokens: model::TokenPair::new(token(token0), token(token1)).unwrap(),
+                reserves: (1, 1),
+                fee: num::rational::Ratio::new(3, 1000),
+                settlement_handling: CapturingSettlementHandler::arc(),
+            })
+        };
+        let mut auction = Auction {
+            orders: vec![LimitOrder {
+                sell_token: token(1),
+                buy_token: token(2),
+                ..Default::default()
+            }],
+            liquidity: vec![pool(1, 2), pool(2, 3), pool(3, 4), pool(4, 1), pool(5, 6)],
+            ..Default::default()
+        };
+
+        auction.restrict_liquidity_to_order_tokens();
+        assert_eq!(auction.liquidity, vec![pool(1, 2), pool(2, 3), pool(4, 1)]);
+    }
+
+    #[tokio::test]
+    async fn decorators_filter_orders_before_solving() {
+        struct OrderCapturingSolver(Mutex<Vec<LimitOrder>>);
+        #[async_trait::async_trait]
+        impl Solver for OrderCapturingSolver {
+            async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+                *self.0.lock().unwrap() = auction.orders;
+                Ok(Vec::new())
+            }
+
+            fn account(&self) -> &Account {
+                unimplemented!()
+            }
+
+            fn name(&self) -> &'static str {
+                "OrderCapturingSolver"
+            }
+        }
+
+        let allowed = H160::from_low_u64_be(1);
+        let other = H160::from_low_u64_be(2);
+        let order = |sell_token, amount: u64| LimitOrder {
+            id: format!("{:?}-{}", sell_token, amount),
+            sell_token,
+            buy_token: allowed,
+            sell_amount: amount.into(),
+            buy_amount: amount.into(),
+            ..Default::default()
+        };
+        let orders = vec![order(allowed, 1), order(allowed, 100), order(other, 100)];
+        let auction = Auction {
+            orders: orders.clone(),
+            external_prices: externalprices! {
+                native_token: allowed,
+                other => BigRational::one(),
+            },
+            ..Default::default()
+        };
+
+        let inner = Arc::new(OrderCapturingSolver(Default::default()));
+        let solver = SolverDecorators {
+            token_allowlist: Some(hashset! { allowed }),
+            min_order_volume: Some(10.into()),
+            volume_filter_mode: VolumeFilterMode::Either,
+            timeout: Some(Duration::from_secs(1)),
+        }
+        .apply(inner.clone(), Arc::new(NoopMetrics::default()));
+        assert_eq!(solver.name(), "OrderCapturingSolver");
+        solver.solve(auction.clone()).await.unwrap();
+        assert_eq!(*inner.0.lock().unwrap(), [orders[1].clone()]);
+
+        let solver =
+            SolverDecorators::default().apply(inner.clone(), Arc::new(NoopMetrics::default()));
+        solver.solve(auction).await.unwrap();
+        assert_eq!(*inner.0.lock().unwrap(), orders);
+    }
+
+    #[tokio::test]
+    async fn dry_run_solver_returns_no_settlements() {
+        let solver = DryRunSolver::new(
+            Arc::new(SettlingSolver(2)),
+            Arc::new(NoopMetrics::default()),
+        );
+        assert_eq!(solver.name(), "SettlingSolver");
+        assert!(solver.solve(Default::default()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn max_orders_solver_skips_large_auctions() {
+        let solver = MaxOrdersSolver::new(
+            Arc::new(SettlingSolver(1)),
+            2,
+            Arc::new(NoopMetrics::default()),
+        );
+        assert_eq!(solver.name(), "SettlingSolver");
+        let auction = |orders: usize| Auction {
+            orders: vec![LimitOrder::default(); orders],
+            ..Default::default()
+        };
+        assert_eq!(solver.solve(auction(2)).await.unwrap().len(), 1);
+        assert!(solver.solve(auction(3)).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn gas_price_scaling_solver_scales_gas_price() {
+        #[derive(Default)]
+        struct GasPriceRecordingSolver(std::sync::Mutex<Option<f64>>);
+        #[async_trait::async_trait]
+        impl Solver for GasPriceRecordingSolver {
+            async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+                *self.0.lock().unwrap() = Some(auction.gas_price);
+                Ok(Vec::new())
+            }
+
+            fn account(&self) -> &Account {
+                unimplemented!()
+            }
+
+            fn name(&self) -> &'static str {
+                "GasPriceRecordingSolver"
+            }
+        }
+
+        let inner = Arc::new(GasPriceRecordingSolver::default());
+        let solver = GasPriceScalingSolver::new(inner.clone(), 1.5);
+        assert_eq!(solver.name(), "GasPriceRecordingSolver");
+        solver
+            .solve(Auction {
+                gas_price: 2e9,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(*inner.0.lock().unwrap(), Some(3e9));
+    }
+
+    #[test]
+    fn prioritized_solver_priority_survives_wrapping() {
+        let solver = dummy_arc_solver();
+        assert_eq!(solver.priority(), 0);
+
+        let solver: Arc<dyn Solver> = Arc::new(PrioritizedSolver::new(solver, 3));
+        let solver = ShufflingSolver::new(solver, Some(0));
+        assert_eq!(solver.name(), "DummySolver");
+        assert_eq!(solver.priority(), 3);
+    }
+
+    #[test]
+    fn auction_fingerprint_ignores_id_and_ordering() {
+        let token = H160::from_low_u64_be;
+        let order = |id: &str| LimitOrder {
+            id: id.to_string(),
+            sell_token: token(1),
+            buy_token: token(2),
+            ..Default::default()
+        };
+        let pool = |reserve| {
+            Liquidity::ConstantProduct(ConstantProductOrder {
+                tokens: model::TokenPair::new(token(1), token(2)).unwrap(),
+                reserves: (reserve, reserve),
+                fee: num::rational::Ratio::new(3, 1000),
+                settlement_handling: CapturingSettlementHandler::arc(),
+            })
+        };
+        let auction = Auction {
+            id: 1,
+            orders: vec![order("a"), order("b")],
+            liquidity: vec![pool(1), pool(2)],
+            gas_price: 1e9,
+            external_prices: externalprices! {
+                native_token: token(1),
+                token(2) => BigRational::one(),
+            },
+            ..Default::default()
+        };
+
+        let reordered = Auction {
+            id: 2,
+            orders: vec![order("b"), order("a")],
+            liquidity: vec![pool(2), pool(1)],
+            ..auction.clone()
+        };
+        assert_eq!(auction.fingerprint(), reordered.fingerprint());
+
+        let different = Auction {
+            orders: vec![order("a")],
+            ..auction.clone()
+        };
+        assert_ne!(auction.fingerprint(), different.fingerprint());
+
+        let partially_filled = Auction {
+            orders: vec![
+                order("a"),
+                LimitOrder {
+                    sell_amount: 1.into(),
+                    ..order("b")
+                },
+            ],
+            ..auction.clone()
+        };
+        assert_ne!(auction.fingerprint(), partially_filled.fingerprint());
+
+        let with_fee = Auction {
+            fee_policy: FeePolicy::from_volume_bps(Some(5)),
+            ..auction.clone()
+        };
+        assert_ne!(auction.fingerprint(), with_fee.fingerprint());
+    }
+
+    #[tokio::test]
+    async fn combined_solver_returns_settlements_of_all_inner_solvers() {
+        let solver = |solvers: Vec<Arc<dyn Solver>>| CombinedSolver::new("Combined", solvers);
+        let failing = || Arc::new(FailingSolver(1.into()));
+
+        let combined = solver(vec![
+            Arc::new(SettlingSolver(1)),
+            Arc::new(SettlingSolver(2)),
+        ]);
+        assert_eq!(combined.name(), "Combined");
+        assert_eq!(combined.solve(Default::default()).await.unwrap().len(), 3);
+
+        let combined = solver(vec![Arc::new(SettlingSolver(2)), failing()]);
+        assert_eq!(combined.solve(Default::default()).await.unwrap().len(), 2);
+
+        let combined = solver(vec![failing()]);
+        assert!(combined.solve(Default::default()).await.is_err());
+    }
+
+    #[test]
+    fn combined_solver_uses_highest_inner_priority() {
+        let combined = CombinedSolver::new(
+            "Combined",
+            vec![
+                Arc::new(PrioritizedSolver::new(dummy_arc_solver(), -1)),
+                Arc::new(PrioritizedSolver::new(dummy_arc_solver(), 2)),
+                dummy_arc_solver(),
+            ],
+        );
+        assert_eq!(combined.priority(), 2);
     }
 }
@@ -217,6 +217,10 @@ impl Interaction for BatchSwap {
 
         vec![(self.vault.address(), 0.into(), Bytes(calldata))]
     }
+
+    fn kind(&self) -> &'static str {
+        "BalancerSorBatchSwap"
+    }
 }
 
 #[cfg(test)]
@@ -381,6 +381,7 @@ impl Solver for HttpSolver {
             gas_price,
             deadline,
             external_prices,
+            ..
         }: Auction,
     ) -> Result<Vec<Settlement>> {
         if orders.is_empty() {
@@ -490,6 +491,7 @@ mod tests {
                 chain_id: 0,
                 base: url.parse().unwrap(),
                 client: Client::new(),
+                retry_policy: Default::default(),
                 config: SolverConfig {
                     api_key: None,
                     max_nr_exec_orders: 0,
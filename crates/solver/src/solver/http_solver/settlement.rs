@@ -129,6 +129,10 @@ impl Interaction for InteractionData {
     fn encode(&self) -> Vec<EncodedInteraction> {
         vec![(self.target, self.value, Bytes(self.call_data.clone()))]
     }
+
+    fn kind(&self) -> &'static str {
+        "HttpSolverInteraction"
+    }
 }
 
 impl IntermediateSettlement {
@@ -21,6 +21,7 @@ use maplit::hashmap;
 use model::order::OrderKind;
 use reqwest::Client;
 use reqwest::Url;
+use shared::http_retry::HttpRetryPolicy;
 use shared::oneinch_api::{
     OneInchClient, OneInchClientImpl, ProtocolCache, RestError, RestResponse, Swap, SwapQuery,
 };
@@ -53,6 +54,7 @@ impl From<RestError> for SettlementError {
 
 impl OneInchSolver {
     /// Creates a new 1Inch solver with a list of disabled protocols.
+    #[allow(clippy::too_many_arguments)]
     pub fn with_disabled_protocols(
         account: Account,
         web3: Web3,
@@ -60,6 +62,7 @@ impl OneInchSolver {
         chain_id: u64,
         disabled_protocols: impl IntoIterator<Item = String>,
         client: Client,
+        retry_policy: HttpRetryPolicy,
         one_inch_url: Url,
     ) -> Result<Self> {
         let settlement_address = settlement_contract.address();
@@ -67,7 +70,10 @@ impl OneInchSolver {
             account,
             settlement_contract,
             disabled_protocols: disabled_protocols.into_iter().collect(),
-            client: Box::new(OneInchClientImpl::new(one_inch_url, client, chain_id)?),
+            client: Box::new(
+                OneInchClientImpl::new(one_inch_url, client, chain_id)?
+                    .with_retry_policy(retry_policy),
+            ),
             allowance_fetcher: Box::new(AllowanceManager::new(web3, settlement_address)),
             protocol_cache: ProtocolCache::default(),
         })
@@ -136,6 +142,10 @@ impl Interaction for Swap {
     fn encode(&self) -> Vec<EncodedInteraction> {
         vec![(self.tx.to, self.tx.value, Bytes(self.tx.data.clone()))]
     }
+
+    fn kind(&self) -> &'static str {
+        "OneInchSwap"
+    }
 }
 
 #[async_trait::async_trait]
@@ -425,6 +435,7 @@ mod tests {
             chain_id,
             vec!["PMM1".to_string()],
             Client::new(),
+            Default::default(),
             OneInchClientImpl::DEFAULT_URL.try_into().unwrap(),
         )
         .unwrap();
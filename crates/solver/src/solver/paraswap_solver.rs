@@ -15,6 +15,7 @@ use ethcontract::{Account, Bytes, H160, U256};
 use maplit::hashmap;
 use model::order::OrderKind;
 use reqwest::Client;
+use shared::http_retry::HttpRetryPolicy;
 use shared::paraswap_api::{
     DefaultParaswapApi, ParaswapApi, ParaswapResponseError, PriceQuery, PriceResponse, Side,
     TradeAmount, TransactionBuilderQuery, TransactionBuilderResponse,
@@ -52,6 +53,7 @@ impl ParaswapSolver {
         slippage_bps: u32,
         disabled_paraswap_dexs: Vec<String>,
         client: Client,
+        retry_policy: HttpRetryPolicy,
         partner: Option<String>,
     ) -> Self {
         let allowance_fetcher = AllowanceManager::new(web3, settlement_contract.address());
@@ -64,6 +66,7 @@ impl ParaswapSolver {
             client: Box::new(DefaultParaswapApi {
                 client,
                 partner: partner.unwrap_or_else(|| REFERRER.into()),
+                retry_policy,
             }),
             slippage_bps,
             disabled_paraswap_dexs,
@@ -195,6 +198,10 @@ impl Interaction for TransactionBuilderResponse {
     fn encode(&self) -> Vec<EncodedInteraction> {
         vec![(self.to, self.value, Bytes(self.data.0.clone()))]
     }
+
+    fn kind(&self) -> &'static str {
+        "ParaswapSwap"
+    }
 }
 
 #[cfg(test)]
@@ -538,6 +545,7 @@ mod tests {
             1,
             vec![],
             Client::new(),
+            Default::default(),
             None,
         );
 
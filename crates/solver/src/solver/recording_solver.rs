@@ -0,0 +1,268 @@
+use crate::{
+    liquidity::{ConstantProductOrder, Exchange, LimitOrder, Liquidity, SettlementHandling},
+    settlement::{external_prices::ExternalPrices, Settlement},
+    solver::{Auction, Solver},
+};
+use anyhow::{Context as _, Result};
+use ethcontract::{Account, H160, U256};
+use model::{order::OrderKind, TokenPair};
+use num::rational::Ratio;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// A solver that writes every auction it receives as JSON to a directory before passing it on to
+/// the inner solver. Recorded auctions can be loaded again with `load_auction`.
+pub struct RecordingSolver {
+    inner: Arc<dyn Solver>,
+    directory: PathBuf,
+}
+
+impl RecordingSolver {
+    pub fn new(inner: Arc<dyn Solver>, directory: PathBuf) -> Self {
+        Self { inner, directory }
+    }
+
+    fn record(&self, auction: &Auction) -> Result<()> {
+        let path = auction_path(&self.directory, auction.id);
+        let json = serde_json::to_vec(&RecordedAuction::from(auction))?;
+        std::fs::write(&path, json).with_context(|| format!("failed to write {:?}", path))
+    }
+}
+
+#[async_trait::async_trait]
+impl Solver for RecordingSolver {
+    async fn solve(&self, auction: Auction) -> Result<Vec<Settlement>> {
+        if let Err(err) = self.record(&auction) {
+            tracing::warn!(?err, "failed to record auction {}", auction.id);
+        }
+        self.inner.solve(auction).await
+    }
+
+    fn account(&self) -> &Account {
+        self.inner.account()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.inner.priority()
+    }
+}
+
+/// Returns the path at which the auction with the specified id gets recorded.
+pub fn auction_path(directory: &Path, id: u64) -> PathBuf {
+    directory.join(format!("auction_{}.json", id))
+}
+
+/// Loads an auction that was recorded by a `RecordingSolver`.
+///
+/// Settlement handlers can't be recorded so the loaded orders and constant product pools use the
+/// specified ones instead. Balancer pools are not recorded. The deadline of the loaded auction is
+/// the default one.
+pub fn load_auction(
+    path: &Path,
+    order_handling: Arc<dyn SettlementHandling<LimitOrder>>,
+    amm_handling: Arc<dyn SettlementHandling<ConstantProductOrder>>,
+) -> Result<Auction> {
+    let json = std::fs::read(path).with_context(|| format!("failed to read {:?}", path))?;
+    let recorded: RecordedAuction = serde_json::from_slice(&json)?;
+    let limit_order = |order: RecordedLimitOrder| LimitOrder {
+        id: order.id,
+        sell_token: order.sell_token,
+        buy_token: order.buy_token,
+        sell_amount: order.sell_amount,
+        buy_amount: order.buy_amount,
+        kind: order.kind,
+        partially_fillable: order.partially_fillable,
+        unscaled_subsidized_fee: order.unscaled_subsidized_fee,
+        scaled_unsubsidized_fee: order.scaled_unsubsidized_fee,
+        is_liquidity_order: order.is_liquidity_order,
+        settlement_handling: order_handling.clone(),
+        exchange: match order.exchange {
+            RecordedExchange::GnosisProtocol => Exchange::GnosisProtocol,
+            RecordedExchange::ZeroEx => Exchange::ZeroEx,
+        },
+    };
+    let liquidity = recorded
+        .liquidity
+        .into_iter()
+        .map(|liquidity| {
+            Ok(match liquidity {
+                RecordedLiquidity::ConstantProduct {
+                    tokens,
+                    reserves,
+                    fee,
+                } => Liquidity::ConstantProduct(ConstantProductOrder {
+                    tokens: TokenPair::new(tokens.0, tokens.1)
+                        .context("invalid constant product token pair")?,
+                    reserves: (reserves.0.as_u128(), reserves.1.as_u128()),
+                    fee: Ratio::new(fee.0, fee.1),
+                    settlement_handling: amm_handling.clone(),
+                }),
+                RecordedLiquidity::LimitOrder(order) => Liquidity::LimitOrder(limit_order(order)),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Auction {
+        id: recorded.id,
+        orders: recorded.orders.into_iter().map(limit_order).collect(),
+        liquidity,
+        gas_price: recorded.gas_price,
+        external_prices: recorded.external_prices,
+        ..Default::default()
+    })
+}
+
+#[derive(Deserialize, Serialize)]
+struct RecordedAuction {
+    id: u64,
+    orders: Vec<RecordedLimitOrder>,
+    liquidity: Vec<RecordedLiquidity>,
+    gas_price: f64,
+    external_prices: ExternalPrices,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RecordedLimitOrder {
+    id: String,
+    sell_token: H160,
+    buy_token: H160,
+    sell_amount: U256,
+    buy_amount: U256,
+    kind: OrderKind,
+    partially_fillable: bool,
+    unscaled_subsidized_fee: U256,
+    scaled_unsubsidized_fee: U256,
+    is_liquidity_order: bool,
+    exchange: RecordedExchange,
+}
+
+#[derive(Deserialize, Serialize)]
+enum RecordedExchange {
+    GnosisProtocol,
+    ZeroEx,
+}
+
+#[derive(Deserialize, Serialize)]
+enum RecordedLiquidity {
+    ConstantProduct {
+        tokens: (H160, H160),
+        reserves: (U256, U256),
+        fee: (u32, u32),
+    },
+    LimitOrder(RecordedLimitOrder),
+}
+
+impl From<&LimitOrder> for RecordedLimitOrder {
+    fn from(order: &LimitOrder) -> Self {
+        Self {
+            id: order.id.clone(),
+            sell_token: order.sell_token,
+            buy_token: order.buy_token,
+            sell_amount: order.sell_amount,
+            buy_amount: order.buy_amount,
+            kind: order.kind,
+            partially_fillable: order.partially_fillable,
+            unscaled_subsidized_fee: order.unscaled_subsidized_fee,
+            scaled_unsubsidized_fee: order.scaled_unsubsidized_fee,
+            is_liquidity_order: order.is_liquidity_order,
+            exchange: match order.exchange {
+                Exchange::GnosisProtocol => RecordedExchange::GnosisProtocol,
+                Exchange::ZeroEx => RecordedExchange::ZeroEx,
+            },
+        }
+    }
+}
+
+impl From<&Auction> for RecordedAuction {
+    fn from(auction: &Auction) -> Self {
+        Self {
+            id: auction.id,
+            orders: auction.orders.iter().map(From::from).collect(),
+            liquidity: auction
+                .liquidity
+                .iter()
+                .filter_map(|liquidity| match liquidity {
+                    Liquidity::ConstantProduct(amm) => Some(RecordedLiquidity::ConstantProduct {
+                        tokens: amm.tokens.get(),
+                        reserves: (amm.reserves.0.into(), amm.reserves.1.into()),
+                        fee: (*amm.fee.numer(), *amm.fee.denom()),
+                    }),
+                    Liquidity::LimitOrder(order) => {
+                        Some(RecordedLiquidity::LimitOrder(order.into()))
+                    }
+                    Liquidity::BalancerWeighted(_) | Liquidity::BalancerStable(_) => None,
+                })
+                .collect(),
+            gas_price: auction.gas_price,
+            external_prices: auction.external_prices.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::liquidity::tests::CapturingSettlementHandler;
+    use num::{BigRational, One as _};
+
+    #[test]
+    fn recorded_auction_roundtrip() {
+        let token0 = H160::from_low_u64_be(0);
+        let token1 = H160::from_low_u64_be(1);
+        let auction = Auction {
+            id: 42,
+            orders: vec![LimitOrder {
+                id: "order".to_string(),
+                sell_token: token0,
+                buy_token: token1,
+                sell_amount: 1.into(),
+                buy_amount: 2.into(),
+                kind: OrderKind::Buy,
+                ..Default::default()
+            }],
+            liquidity: vec![Liquidity::ConstantProduct(ConstantProductOrder {
+                tokens: TokenPair::new(token0, token1).unwrap(),
+                reserves: (10, 20),
+                fee: Ratio::new(3, 1000),
+                settlement_handling: CapturingSettlementHandler::arc(),
+            })],
+            gas_price: 1e9,
+            external_prices: ExternalPrices::new(
+                token0,
+                maplit::hashmap! { token1 => BigRational::one() },
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let directory =
+            std::env::temp_dir().join(format!("recorded_auction_roundtrip_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let solver = RecordingSolver::new(crate::solver::dummy_arc_solver(), directory.clone());
+        solver.record(&auction).unwrap();
+
+        let loaded = load_auction(
+            &auction_path(&directory, 42),
+            CapturingSettlementHandler::arc(),
+            CapturingSettlementHandler::arc(),
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(loaded.id, auction.id);
+        assert_eq!(loaded.orders, auction.orders);
+        assert_eq!(loaded.liquidity, auction.liquidity);
+        assert_eq!(loaded.gas_price, auction.gas_price);
+        assert_eq!(
+            loaded.external_prices.price(&token1),
+            auction.external_prices.price(&token1)
+        );
+    }
+}
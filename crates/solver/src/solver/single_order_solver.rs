@@ -8,7 +8,12 @@ use anyhow::{Error, Result};
 use ethcontract::Account;
 use primitive_types::U256;
 use rand::prelude::SliceRandom;
-use std::{collections::VecDeque, sync::Arc, time::Duration};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::Semaphore;
 
 #[cfg_attr(test, mockall::automock)]
 #[async_trait::async_trait]
@@ -32,11 +37,24 @@ pub trait SingleOrderSolving: Send + Sync + 'static {
 pub struct SingleOrderSolver<I> {
     inner: I,
     metrics: Arc<dyn SolverMetrics>,
+    max_concurrency: usize,
+    /// Limits the number of concurrent `try_settle_order` calls across all `solve` calls. A single
+    /// `solve` call never runs more than `max_concurrency` workers so this only has an effect when
+    /// the same instance solves several auctions at the same time.
+    semaphore: Semaphore,
 }
 
 impl<I: SingleOrderSolving> SingleOrderSolver<I> {
-    pub fn new(inner: I, metrics: Arc<dyn SolverMetrics>) -> Self {
-        Self { inner, metrics }
+    /// Creates a new single order solver that tries to settle at most `max_concurrency` orders
+    /// at the same time.
+    pub fn new(inner: I, metrics: Arc<dyn SolverMetrics>, max_concurrency: usize) -> Self {
+        let max_concurrency = max_concurrency.max(1);
+        Self {
+            inner,
+            metrics,
+            max_concurrency,
+            semaphore: Semaphore::new(max_concurrency),
+        }
     }
 }
 
@@ -48,25 +66,41 @@ impl<I: SingleOrderSolving> Solver for SingleOrderSolver<I> {
         // Randomize which orders we start with to prevent us getting stuck on bad orders.
         orders.shuffle(&mut rand::thread_rng());
 
-        let mut orders = orders
-            .into_iter()
-            .filter(|order| !order.is_liquidity_order)
-            .collect::<VecDeque<_>>();
-        let mut settlements = Vec::new();
-        let settle = async {
-            while let Some(order) = orders.pop_front() {
-                match self.inner.try_settle_order(order.clone(), &auction).await {
+        let orders = Mutex::new(
+            orders
+                .into_iter()
+                .filter(|order| !order.is_liquidity_order)
+                .collect::<VecDeque<_>>(),
+        );
+        self.metrics
+            .single_order_solver_attempted(self.inner.name(), orders.lock().unwrap().len());
+        let settlements = Mutex::new(Vec::new());
+        let (orders_ref, settlements_ref, auction_ref) = (&orders, &settlements, &auction);
+        let worker = move || async move {
+            let (orders, settlements, auction) = (orders_ref, settlements_ref, auction_ref);
+            loop {
+                // Don't hold the lock while settling so that other workers can take orders.
+                let next_order = orders.lock().unwrap().pop_front();
+                let order = match next_order {
+                    Some(order) => order,
+                    None => break,
+                };
+                let result = {
+                    let _permit = self.semaphore.acquire().await.expect("semaphore closed");
+                    self.inner.try_settle_order(order.clone(), auction).await
+                };
+                match result {
                     Ok(settlement) => {
                         self.metrics
                             .single_order_solver_succeeded(self.inner.name());
-                        settlements.extend(settlement)
+                        settlements.lock().unwrap().extend(settlement)
                     }
                     Err(err) => {
                         let name = self.inner.name();
                         self.metrics.single_order_solver_failed(name);
                         if err.retryable {
                             tracing::warn!("Solver {} retryable error: {:?}", name, &err.inner);
-                            orders.push_back(order);
+                            orders.lock().unwrap().push_back(order);
                         } else {
                             tracing::warn!("Solver {} error: {:?}", name, &err.inner);
                         }
@@ -74,11 +108,12 @@ impl<I: SingleOrderSolving> Solver for SingleOrderSolver<I> {
                 }
             }
         };
+        let settle = futures::future::join_all((0..self.max_concurrency).map(|_| worker()));
 
         // Subtract a small amount of time to ensure that the driver doesn't reach the deadline first.
         let _ = tokio::time::timeout_at((auction.deadline - Duration::from_secs(1)).into(), settle)
             .await;
-        Ok(settlements)
+        Ok(settlements.into_inner().unwrap())
     }
 
     fn account(&self) -> &Account {
@@ -138,7 +173,7 @@ mod tests {
         inner.expect_name().returning(|| "Mock Solver");
 
         let solver: SingleOrderSolver<_> =
-            SingleOrderSolver::new(inner, Arc::new(NoopMetrics::default()));
+            SingleOrderSolver::new(inner, Arc::new(NoopMetrics::default()), 1);
         let handler = Arc::new(CapturingSettlementHandler::default());
         let order = LimitOrder {
             settlement_handling: handler.clone(),
@@ -188,7 +223,7 @@ mod tests {
             });
 
         let solver: SingleOrderSolver<_> =
-            SingleOrderSolver::new(inner, Arc::new(NoopMetrics::default()));
+            SingleOrderSolver::new(inner, Arc::new(NoopMetrics::default()), 1);
         let handler = Arc::new(CapturingSettlementHandler::default());
         let order = LimitOrder {
             settlement_handling: handler.clone(),
@@ -215,7 +250,7 @@ mod tests {
         });
 
         let solver: SingleOrderSolver<_> =
-            SingleOrderSolver::new(inner, Arc::new(NoopMetrics::default()));
+            SingleOrderSolver::new(inner, Arc::new(NoopMetrics::default()), 1);
         let handler = Arc::new(CapturingSettlementHandler::default());
         let order = LimitOrder {
             settlement_handling: handler.clone(),
@@ -230,6 +265,88 @@ mod tests {
             .unwrap();
     }
 
+    /// Single order solver keeping track of the maximum number of concurrent calls.
+    #[derive(Default)]
+    struct ConcurrencyTrackingSolver {
+        current: std::sync::atomic::AtomicUsize,
+        max: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl SingleOrderSolving for ConcurrencyTrackingSolver {
+        async fn try_settle_order(
+            &self,
+            _: LimitOrder,
+            _: &Auction,
+        ) -> Result<Option<Settlement>, SettlementError> {
+            use std::sync::atomic::Ordering;
+            let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(current, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.current.fetch_sub(1, Ordering::SeqCst);
+            Ok(None)
+        }
+
+        fn account(&self) -> &Account {
+            unimplemented!()
+        }
+    }
+
+    // Time is paused so that the sleeping solver calls only complete once all workers are blocked
+    // which makes the maximum concurrency deterministic.
+    #[tokio::test(start_paused = true)]
+    async fn limits_concurrency() {
+        let solver = SingleOrderSolver::new(
+            ConcurrencyTrackingSolver::default(),
+            Arc::new(NoopMetrics::default()),
+            3,
+        );
+        let orders = (0..10)
+            .map(|i| LimitOrder {
+                id: i.to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        solver
+            .solve(Auction {
+                orders,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            solver.inner.max.load(std::sync::atomic::Ordering::SeqCst),
+            3
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limits_concurrency_across_solve_calls() {
+        let solver = SingleOrderSolver::new(
+            ConcurrencyTrackingSolver::default(),
+            Arc::new(NoopMetrics::default()),
+            3,
+        );
+        let auction = || Auction {
+            orders: (0..10)
+                .map(|i| LimitOrder {
+                    id: i.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let (first, second) = futures::join!(solver.solve(auction()), solver.solve(auction()));
+        first.unwrap();
+        second.unwrap();
+        assert_eq!(
+            solver.inner.max.load(std::sync::atomic::Ordering::SeqCst),
+            3
+        );
+    }
+
     #[test]
     fn execution_respects_order_() {
         let order = LimitOrder {
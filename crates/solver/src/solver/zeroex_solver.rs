@@ -145,6 +145,10 @@ impl Interaction for SwapResponse {
     fn encode(&self) -> Vec<EncodedInteraction> {
         vec![(self.to, self.value, Bytes(self.data.0.clone()))]
     }
+
+    fn kind(&self) -> &'static str {
+        "ZeroExSwap"
+    }
 }
 
 impl Display for ZeroExSolver {